@@ -3,11 +3,21 @@ use wasm_bindgen::{JsCast, JsValue};
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
 
 use crate::{
-    model::{element::Element, widget_kind::WidgetKind, AppData},
-    rough::Rough,
+    model::{
+        dirty_region::DirtyRegion,
+        element::Element,
+        handle::{get_handles, Handle, SELECTION_MARGIN},
+        primitive::{Op, Primitive},
+        rect::Rect,
+        viewport::Viewport,
+        widget_kind::WidgetKind,
+        AppData,
+    },
+    render_cache,
+    text_layout::{get_display_text, get_text_position},
 };
 
-fn get_context(canvas: &HtmlCanvasElement) -> CanvasRenderingContext2d {
+pub fn get_context(canvas: &HtmlCanvasElement) -> CanvasRenderingContext2d {
     canvas
         .get_context("2d")
         .expect("should get context")
@@ -16,57 +26,177 @@ fn get_context(canvas: &HtmlCanvasElement) -> CanvasRenderingContext2d {
         .expect("should cast to context")
 }
 
-pub fn draw_scene(canvas: HtmlCanvasElement, app_data: &AppData) {
-    clear_canvas(&canvas);
+/// Extra space repainted around a dirty region in screen pixels, for the
+/// parts of the selection UI that don't scale with zoom.
+const DIRTY_MARGIN: i32 = 32;
+
+/**
+ * 只重绘上次绘制后变化的区域，并且跳过不在可见区域内的元素
+ */
+pub fn draw_scene(canvas: HtmlCanvasElement, app_data: &AppData, viewport: &Viewport) {
     let ctx = get_context(&canvas);
-    app_data.elements.iter().for_each(|element| {
-        let rect = element.rect;
-        if element.kind == WidgetKind::Text {
-            draw_text(&canvas, &element);
-        } else if element.kind == WidgetKind::Selection {
-            draw_selection(&canvas, &element)
-        } else {
-            ctx.translate(rect.start_x.into(), rect.start_y.into())
-                .unwrap();
-            element.shape_string.iter().for_each(|shape| {
-                Rough::draw_shape(shape);
-            });
-            ctx.translate((-rect.start_x).into(), (-rect.start_y).into())
-                .unwrap();
+    let pixel_ratio = viewport.pixel_ratio;
+    let screen = Rect::new(
+        0,
+        0,
+        (canvas.width() as f64 / pixel_ratio).ceil() as i32,
+        (canvas.height() as f64 / pixel_ratio).ceil() as i32,
+    );
+    let dirty = app_data.take_dirty();
+    let region = match dirty {
+        DirtyRegion::Clean => return,
+        DirtyRegion::Full => screen,
+        DirtyRegion::Region(rect) => {
+            let rect = viewport.to_screen_rect(rect);
+            Rect::new(
+                (rect.start_x - DIRTY_MARGIN).max(0),
+                (rect.start_y - DIRTY_MARGIN).max(0),
+                (rect.end_x + DIRTY_MARGIN).min(screen.end_x),
+                (rect.end_y + DIRTY_MARGIN).min(screen.end_y),
+            )
+        }
+    };
+    if region.get_width() <= 0 || region.get_height() <= 0 {
+        return;
+    }
+
+    ctx.save();
+    clip_and_clear(&ctx, region, pixel_ratio);
+    ctx.scale(viewport.zoom, viewport.zoom).unwrap();
+    ctx.translate(viewport.scroll_x, viewport.scroll_y).unwrap();
+    let elements = app_data.get_elements_in_rect(viewport.to_scene_rect(region));
+    // 全部重绘时丢弃已经不在屏幕上的元素的缓存
+    if dirty == DirtyRegion::Full {
+        render_cache::retain(&elements);
+    }
+    elements
+        .into_iter()
+        .filter(|element| app_data.get_editing() != Some(&element.id))
+        .for_each(|element| {
+            draw_cached_element(&ctx, element, viewport.zoom * pixel_ratio);
+            if element.is_selected {
+                draw_selection_border(&ctx, element)
+            }
+        });
+    if let Some(element) = app_data.get_transformable_element() {
+        draw_handles(&ctx, element, viewport.zoom);
+    }
+    if let Some(selection_box) = app_data.get_selection_box() {
+        draw_selection(&ctx, selection_box);
+    }
+    ctx.restore();
+}
+
+/**
+ * 只绘制元素本身，不包含选中框等交互状态，导出时也使用这个方法
+ */
+pub fn draw_element(ctx: &CanvasRenderingContext2d, element: &Element) {
+    let (origin_x, origin_y) = element.origin();
+    ctx.save();
+    rotate_context(ctx, element);
+    if element.kind == WidgetKind::Text {
+        draw_text(ctx, element);
+    } else {
+        ctx.translate(origin_x.into(), origin_y.into()).unwrap();
+        draw_shapes(ctx, element);
+    }
+    ctx.restore();
+}
+
+/**
+ * 与 `draw_element` 相同，但使用缓存的位图，只在画布上使用。
+ * `scale` 是场景坐标到设备像素的比例
+ */
+fn draw_cached_element(ctx: &CanvasRenderingContext2d, element: &Element, scale: f64) {
+    let (origin_x, origin_y) = element.origin();
+    ctx.save();
+    rotate_context(ctx, element);
+    if element.kind == WidgetKind::Text {
+        draw_text(ctx, element);
+    } else {
+        ctx.translate(origin_x.into(), origin_y.into()).unwrap();
+        render_cache::draw_cached_shapes(ctx, element, scale);
+    }
+    ctx.restore();
+}
+
+/// Draws the local geometry of the element at the current origin.
+pub fn draw_shapes(ctx: &CanvasRenderingContext2d, element: &Element) {
+    element.shapes.iter().for_each(|shape| {
+        draw_primitive(ctx, shape);
+    });
+}
+
+/**
+ * 让之后的绘制绕元素的中心旋转，需要在外面 save/restore
+ */
+fn rotate_context(ctx: &CanvasRenderingContext2d, element: &Element) {
+    if element.angle == 0.0 {
+        return;
+    }
+    let (center_x, center_y) = element.rect.get_center();
+    ctx.translate(center_x, center_y).unwrap();
+    ctx.rotate(element.angle).unwrap();
+    ctx.translate(-center_x, -center_y).unwrap();
+}
+
+fn draw_primitive(ctx: &CanvasRenderingContext2d, primitive: &Primitive) {
+    match primitive {
+        Primitive::Stroke { ops, color, width } => {
+            ctx.save();
+            ctx.set_stroke_style(&JsValue::from_str(color));
+            ctx.set_line_width(*width);
+            trace_ops(ctx, ops);
+            ctx.stroke();
+            ctx.restore();
         }
-        if element.is_selected {
-            draw_selection_border(&canvas, &element)
+        Primitive::Fill { ops, color } => {
+            ctx.save();
+            ctx.set_fill_style(&JsValue::from_str(color));
+            trace_ops(ctx, ops);
+            ctx.fill();
+            ctx.restore();
         }
-    })
+        Primitive::Text { .. } => (),
+    }
 }
 
-fn draw_text(canvas: &HtmlCanvasElement, element: &Element) {
-    let ctx = get_context(canvas);
-    let font = ctx.font();
-    let fill_style = ctx.fill_style();
-    ctx.set_fill_style(&JsValue::from_str(
-        element.config.item_stroke_color.as_str(),
-    ));
-    ctx.set_font("normal 20px Virgil");
-    let text = element.shape_string.clone();
-    let text = text.first();
-    let text = match text {
+fn trace_ops(ctx: &CanvasRenderingContext2d, ops: &[Op]) {
+    ctx.begin_path();
+    ops.iter().for_each(|op| match *op {
+        Op::Move(x, y) => ctx.move_to(x, y),
+        Op::LineTo(x, y) => ctx.line_to(x, y),
+        Op::BCurveTo(cp1x, cp1y, cp2x, cp2y, x, y) => {
+            ctx.bezier_curve_to(cp1x, cp1y, cp2x, cp2y, x, y)
+        }
+    });
+}
+
+fn draw_text(ctx: &CanvasRenderingContext2d, element: &Element) {
+    let text = match element.get_text() {
         Some(text) => text,
         None => return,
     };
-    let text_measure = ctx.measure_text(&text).unwrap();
-
-    let x = element.rect.start_x;
-    let y = element.rect.start_y + text_measure.font_bounding_box_ascent() as i32;
-
-    ctx.fill_text(text.as_str(), x.into(), y.into()).unwrap();
-    ctx.set_font(font.as_ref());
-    ctx.set_fill_style(fill_style.as_ref());
+    ctx.save();
+    ctx.set_fill_style(&JsValue::from_str(
+        element.config.item_stroke_color.as_str(),
+    ));
+    let config = &element.config;
+    ctx.set_font(&config.get_font());
+    ctx.set_text_baseline("top");
+    ctx.set_text_align(config.text_align.as_str());
+    let (x, top) = get_text_position(element);
+    get_display_text(element, text)
+        .split('\n')
+        .enumerate()
+        .for_each(|(index, line)| {
+            let y = top + index as f64 * config.get_line_height();
+            ctx.fill_text(line, x, y).unwrap();
+        });
+    ctx.restore();
 }
 
-fn draw_selection(canvas: &HtmlCanvasElement, element: &Element) {
-    let ctx = get_context(canvas);
-    let rect = element.rect;
+fn draw_selection(ctx: &CanvasRenderingContext2d, rect: Rect) {
     let fill_style = ctx.fill_style();
     ctx.set_fill_style(&JsValue::from_str("rgba(0, 0, 255, 0.10)"));
     ctx.fill_rect(
@@ -78,12 +208,12 @@ fn draw_selection(canvas: &HtmlCanvasElement, element: &Element) {
     ctx.set_fill_style(fill_style.as_ref());
 }
 
-fn draw_selection_border(canvas: &HtmlCanvasElement, element: &Element) {
-    let ctx = get_context(canvas);
+fn draw_selection_border(ctx: &CanvasRenderingContext2d, element: &Element) {
     let dash = JsValue::from_serde(&[8.0]).unwrap();
-    let line_dash = ctx.get_line_dash();
+    ctx.save();
+    rotate_context(ctx, element);
     ctx.set_line_dash(&dash).unwrap();
-    let margin = 4;
+    let margin = SELECTION_MARGIN;
     let x = element.rect.start_x.min(element.rect.end_x);
     let y = element.rect.start_y.min(element.rect.end_y);
     let width = (element.rect.end_x - element.rect.start_x).abs();
@@ -94,17 +224,54 @@ fn draw_selection_border(canvas: &HtmlCanvasElement, element: &Element) {
         (width + margin * 2).into(),
         (height + margin * 2).into(),
     );
-    ctx.set_line_dash(line_dash.as_ref()).unwrap();
+    ctx.restore();
 }
 
-fn clear_canvas(canvas: &HtmlCanvasElement) {
-    let ctx = canvas
-        .get_context("2d")
-        .expect("should get context")
-        .unwrap()
-        .dyn_into::<web_sys::CanvasRenderingContext2d>()
-        .expect("should cast to context");
-    let canvas_width = canvas.width();
-    let canvas_height = canvas.height();
-    ctx.clear_rect(-0.5, -0.5, canvas_width as f64, canvas_height as f64);
+fn draw_handles(ctx: &CanvasRenderingContext2d, element: &Element, zoom: f64) {
+    ctx.save();
+    rotate_context(ctx, element);
+    ctx.set_fill_style(&JsValue::from_str("#ffffff"));
+    ctx.set_line_width(1.0 / zoom);
+    get_handles(element, zoom)
+        .iter()
+        .for_each(|(handle, area)| {
+            let (x, y) = (area.start_x.into(), area.start_y.into());
+            let (width, height) = (area.get_width().into(), area.get_height().into());
+            if *handle == Handle::Rotation {
+                let (center_x, center_y) = area.get_center();
+                ctx.begin_path();
+                ctx.arc(center_x, center_y, width / 2.0, 0.0, std::f64::consts::TAU)
+                    .unwrap();
+                ctx.fill();
+                ctx.stroke();
+            } else {
+                ctx.fill_rect(x, y, width, height);
+                ctx.stroke_rect(x, y, width, height);
+            }
+        });
+    ctx.restore();
+}
+
+/**
+ * 之后的绘制只影响 `region`，坐标为 css 像素，并设置好设备像素的缩放。
+ * 需要在外面 save/restore
+ */
+fn clip_and_clear(ctx: &CanvasRenderingContext2d, region: Rect, pixel_ratio: f64) {
+    let x = region.start_x as f64 * pixel_ratio;
+    let y = region.start_y as f64 * pixel_ratio;
+    let width = region.get_width() as f64 * pixel_ratio;
+    let height = region.get_height() as f64 * pixel_ratio;
+    ctx.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0).unwrap();
+    ctx.begin_path();
+    ctx.rect(x, y, width, height);
+    ctx.clip();
+    ctx.clear_rect(x, y, width, height);
+    // 1 像素宽的线在奇数倍的像素比下需要偏移半个像素才清晰
+    let offset = if pixel_ratio.round() as i32 % 2 == 1 {
+        0.5
+    } else {
+        0.0
+    };
+    ctx.set_transform(pixel_ratio, 0.0, 0.0, pixel_ratio, offset, offset)
+        .unwrap();
 }
@@ -0,0 +1,131 @@
+use crate::{
+    model::{
+        element::Element,
+        font::TextAlign,
+        primitive::{Op, Primitive},
+        rect::Rect,
+        widget_kind::WidgetKind,
+    },
+    text_layout::{get_display_text, get_text_position},
+};
+
+/**
+ * 将元素绘制为 svg，`area` 为导出区域，`background` 为空时背景透明
+ */
+pub fn draw_svg(elements: &[&Element], area: Rect, background: Option<&str>) -> String {
+    let width = area.get_width();
+    let height = area.get_height();
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1" width="{width}" height="{height}" viewBox="{} {} {width} {height}">"#,
+        area.start_x, area.start_y,
+    );
+    if let Some(background) = background {
+        svg.push_str(&format!(
+            r#"<rect x="{}" y="{}" width="{width}" height="{height}" fill="{}"/>"#,
+            area.start_x,
+            area.start_y,
+            escape(background),
+        ));
+    }
+    elements.iter().for_each(|element| {
+        if element.kind == WidgetKind::Text {
+            svg.push_str(&draw_text(element));
+        } else {
+            let (origin_x, origin_y) = element.origin();
+            svg.push_str(&format!(
+                r#"<g transform="{}translate({origin_x} {origin_y})">"#,
+                rotate_transform(element),
+            ));
+            element
+                .shapes
+                .iter()
+                .for_each(|shape| svg.push_str(&draw_primitive(shape)));
+            svg.push_str("</g>");
+        }
+    });
+    svg.push_str("</svg>");
+    svg
+}
+
+fn draw_primitive(primitive: &Primitive) -> String {
+    match primitive {
+        Primitive::Stroke { ops, color, width } => format!(
+            r#"<path d="{}" stroke="{}" stroke-width="{width}" fill="none"/>"#,
+            path_data(ops),
+            escape(color),
+        ),
+        Primitive::Fill { ops, color } => format!(
+            r#"<path d="{}" stroke="none" fill="{}"/>"#,
+            path_data(ops),
+            escape(color),
+        ),
+        Primitive::Text { .. } => String::new(),
+    }
+}
+
+fn draw_text(element: &Element) -> String {
+    let text = match element.get_text() {
+        Some(text) => text,
+        None => return String::new(),
+    };
+    let config = &element.config;
+    let (x, top) = get_text_position(element);
+    let lines: String = get_display_text(element, text)
+        .split('\n')
+        .enumerate()
+        .map(|(index, line)| {
+            let y = top + index as f64 * config.get_line_height();
+            format!(r#"<tspan x="{x}" y="{y}">{}</tspan>"#, escape(line))
+        })
+        .collect();
+    format!(
+        r#"<text transform="{}" font-family="{}" font-size="{}px" text-anchor="{}" fill="{}" dominant-baseline="text-before-edge" xml:space="preserve">{lines}</text>"#,
+        rotate_transform(element),
+        config.font_family.css_name(),
+        config.font_size,
+        get_text_anchor(config.text_align),
+        escape(&config.item_stroke_color),
+    )
+}
+
+fn get_text_anchor(text_align: TextAlign) -> &'static str {
+    match text_align {
+        TextAlign::Left => "start",
+        TextAlign::Center => "middle",
+        TextAlign::Right => "end",
+    }
+}
+
+/**
+ * 绕元素中心旋转，与画布上的绘制一致
+ */
+fn rotate_transform(element: &Element) -> String {
+    if element.angle == 0.0 {
+        return String::new();
+    }
+    let (center_x, center_y) = element.rect.get_center();
+    format!(
+        "rotate({} {center_x} {center_y}) ",
+        element.angle.to_degrees()
+    )
+}
+
+fn path_data(ops: &[Op]) -> String {
+    ops.iter()
+        .map(|op| match *op {
+            Op::Move(x, y) => format!("M{x:.2} {y:.2}"),
+            Op::LineTo(x, y) => format!("L{x:.2} {y:.2}"),
+            Op::BCurveTo(cp1x, cp1y, cp2x, cp2y, x, y) => {
+                format!("C{cp1x:.2} {cp1y:.2} {cp2x:.2} {cp2y:.2} {x:.2} {y:.2}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,37 @@
+use wasm_bindgen_futures::JsFuture;
+use web_sys::FontFace;
+
+/// Fonts copied from `assets/fonts` next to `index.html` by Trunk, so they
+/// load from the same place as the app. The sources are tried in order, so
+/// Virgil still loads from its old remote address while the file is missing.
+const FONTS: [(&str, &str); 2] = [
+    (
+        "Virgil",
+        "url(fonts/Virgil.woff2) format(\"woff2\"), \
+         url(https://uploads.codesandbox.io/uploads/user/ed077012-e728-4a42-8395-cbd299149d62/AflB-FG_Virgil.ttf)",
+    ),
+    ("Cascadia", "url(fonts/Cascadia.woff2) format(\"woff2\")"),
+];
+
+/**
+ * 加载打包的字体，全部加载完成或失败后返回。
+ * 加载失败时文字使用后备字体绘制，不影响使用
+ */
+pub async fn load_fonts() {
+    let window = web_sys::window().expect("no global `window` exists");
+    let document = window.document().expect("should have a document on window");
+    let font_set = document.fonts();
+    let loading: Vec<_> = FONTS
+        .iter()
+        .filter_map(|(family, source)| {
+            let font = FontFace::new_with_str(family, source).ok()?;
+            font_set.add(&font).ok()?;
+            Some((family, font.load().ok()?))
+        })
+        .collect();
+    for (family, promise) in loading {
+        if let Err(err) = JsFuture::from(promise).await {
+            tracing::warn!("can't load font {}: {:?}", family, err);
+        }
+    }
+}
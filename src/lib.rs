@@ -1,9 +1,13 @@
 pub mod draw_scene;
+pub mod draw_svg;
 pub mod event;
+pub mod fonts;
 pub mod model;
+pub mod render_cache;
 pub mod rough;
 pub mod storage;
 pub mod store;
+pub mod text_layout;
 pub mod utils;
 pub mod view;
 pub mod widget;
@@ -1,84 +1,141 @@
 use lib::{
     event::add_event_listener,
+    fonts::load_fonts,
     model::{
         element::{Element, ElementConfig},
+        font::{FontFamily, TextAlign, DEFAULT_FONT_SIZE},
+        handle::{get_rotation, resize_rect, Handle},
+        id::ElementId,
         rect::Rect,
+        transform::Transform,
+        viewport::Viewport,
         widget_kind::WidgetKind,
         AppData,
     },
     storage,
-    store::AppState,
-    view::{config_bar::ConfigBar, export::ExportTool, toolbar::Toolbar},
+    store::{AppState, ExportConfig},
+    view::{
+        config_bar::ConfigBar, export::ExportTool, file::FileTool, text_editor::TextEditor,
+        toolbar::Toolbar,
+    },
     widget::create_widget,
 };
-use sycamore::prelude::*;
+use std::{cell::Cell, collections::HashMap, rc::Rc};
+use sycamore::{futures::spawn_local_scoped, prelude::*};
 use wasm_bindgen::JsCast;
-use web_sys::{FontFace, HtmlCanvasElement, KeyboardEvent, MouseEvent};
+use web_sys::{KeyboardEvent, MouseEvent, WheelEvent};
 
 fn main() {
     console_error_panic_hook::set_once();
     tracing_wasm::set_as_global_default();
 
-    let _ = FontFace::new_with_str("Virgil".into(), "url(https://uploads.codesandbox.io/uploads/user/ed077012-e728-4a42-8395-cbd299149d62/AflB-FG_Virgil.ttf)")
-        .unwrap()
-        .load();
-
     sycamore::render(|ctx| view!(ctx, App()));
 }
 
 #[component]
 fn App<'a, G: Html>(ctx: Scope<'a>) -> View<G> {
     let window = web_sys::window().expect("no global `window` exists");
-    let window_width = window.inner_width().unwrap().as_f64().unwrap();
-    let window_height = window.inner_height().unwrap().as_f64().unwrap();
+    let (window_width, window_height) = get_window_size();
+    let pixel_ratio = window.device_pixel_ratio();
 
     let canvas_ref: &NodeRef<G> = create_node_ref(ctx);
 
-    let drawing_state: &Signal<(f64, i32, i32)> = create_signal(ctx, (0.0, 0, 0));
+    let drawing_state: &Signal<(Option<ElementId>, i32, i32)> = create_signal(ctx, (None, 0, 0));
     let is_dragging = create_signal(ctx, (false, 0, 0));
+    // 按住空格或鼠标中键拖动时平移画布，记录上一次的屏幕坐标
+    let panning: &Signal<Option<(i32, i32)>> = create_signal(ctx, None);
+    // 正在拖动的缩放或旋转控制点，以及开始拖动时元素的区域
+    let resizing: &Signal<Option<(ElementId, Handle, Rect)>> = create_signal(ctx, None);
+    let hover_handle: &Signal<Option<Handle>> = create_signal(ctx, None);
+    let is_space_down = create_ref(ctx, Rc::new(Cell::new(false)));
     let app_state = AppState {
         selected_kind: create_rc_signal(WidgetKind::Selection),
-        export_config: create_rc_signal(Default::default()),
+        export_config: create_rc_signal(ExportConfig {
+            // 默认按屏幕的像素比导出，在高分屏上不会模糊
+            scale: (pixel_ratio.ceil() as u32).clamp(1, 3),
+            ..Default::default()
+        }),
         view_bg_color: create_rc_signal("#ffffff".into()),
         item_stroke_color: create_rc_signal("#000000".into()),
         item_bg_color: create_rc_signal("#000000".into()),
+        font_family: create_rc_signal(FontFamily::default()),
+        font_size: create_rc_signal(DEFAULT_FONT_SIZE),
+        text_align: create_rc_signal(TextAlign::default()),
         app_data: create_rc_signal(AppData::get_from_local_storage()),
+        viewport: create_rc_signal(Viewport {
+            pixel_ratio,
+            ..Default::default()
+        }),
+        canvas_size: create_rc_signal((window_width, window_height)),
+        text_editor: create_rc_signal(None),
     };
     let app_state = provide_context(ctx, app_state);
 
-    on_mount(ctx, || {
-        let canvas: HtmlCanvasElement = canvas_ref.get::<DomNode>().unchecked_into();
-        let canvas_ctx = canvas
-            .get_context("2d")
-            .expect("should get context")
-            .unwrap()
-            .dyn_into::<web_sys::CanvasRenderingContext2d>()
-            .expect("should cast to context");
-        canvas_ctx.translate(0.5, 0.5).unwrap();
-        let app = app_state.get_data();
-        app.draw();
+    on_mount(ctx, move || {
+        // 字体加载完成后才第一次绘制，否则文字会先用后备字体绘制
+        spawn_local_scoped(ctx, async {
+            load_fonts().await;
+            let mut app = app_state.get_data();
+            // 标签按加载后的字体重新换行
+            app.layout_bound_texts();
+            app.mark_all_dirty();
+            app.draw(&app_state.viewport.get());
+        });
 
         let app_state_cloned = app_state.clone();
+        let on_resize = move |_| {
+            let window = web_sys::window().expect("should have a window in this context");
+            let (width, height) = get_window_size();
+            app_state_cloned.resize_canvas(width, height, window.device_pixel_ratio());
+        };
+        add_event_listener("resize", on_resize);
+
+        let app_state_cloned = app_state.clone();
+        let space_down = is_space_down.clone();
 
         let handler = move |event: KeyboardEvent| {
-            let step: i32 = event.shift_key().then(|| 10).unwrap_or(1);
+            let step: i32 = if event.shift_key() { 10 } else { 1 };
+            let command_key = event.meta_key() || event.ctrl_key();
             match event.key().as_str() {
+                "z" | "Z" if command_key && event.shift_key() => app_state_cloned.redo(),
+                "z" if command_key => app_state_cloned.undo(),
+                "y" if command_key => app_state_cloned.redo(),
                 "Backspace" | "Delete" => app_state_cloned.delete_selected_elements(),
                 "ArrowLeft" => app_state_cloned.move_selected_elements(-step, 0),
                 "ArrowRight" => app_state_cloned.move_selected_elements(step, 0),
                 "ArrowUp" => app_state_cloned.move_selected_elements(0, -step),
                 "ArrowDown" => app_state_cloned.move_selected_elements(0, step),
                 "Escape" => app_state_cloned.clear_selection_elements(),
+                "Enter" => {
+                    app_state_cloned.edit_selected_text();
+                    // 否则回车会输入到刚获得焦点的编辑器中
+                    if app_state_cloned.text_editor.get().is_some() {
+                        event.prevent_default();
+                    }
+                }
+                "H" if event.shift_key() => app_state_cloned.flip_selected_elements(true),
+                "V" if event.shift_key() => app_state_cloned.flip_selected_elements(false),
                 "a" if event.meta_key() => app_state_cloned.select_all(),
+                " " => space_down.set(true),
                 _ => (),
             };
         };
         add_event_listener("keydown", handler);
 
+        let space_down = is_space_down.clone();
+        let on_keyup = move |event: KeyboardEvent| {
+            if event.key() == " " {
+                space_down.set(false);
+            }
+        };
+        add_event_listener("keyup", on_keyup);
+
         let app_state_cloned = app_state.clone();
         let on_copy = move |_| {
             storage::save_elements_to_clipboard(
-                &app_state_cloned.get_data().get_selected_elements(),
+                &app_state_cloned
+                    .get_data()
+                    .get_selected_elements_with_labels(),
             );
         };
         add_event_listener("copy", on_copy);
@@ -87,12 +144,34 @@ fn App<'a, G: Html>(ctx: Scope<'a>) -> View<G> {
         let on_paste = move |_| {
             if let Some(elements) = storage::read_elements_from_clipboard() {
                 let mut app = app_state_cloned.get_data();
-                elements.iter().for_each(|element| {
-                    let mut element = Element::from(element);
-                    element.move_element(10, 10);
-                    app.add_element(element);
+                app.begin_change();
+                let mut pasted: Vec<Element> = elements
+                    .iter()
+                    .map(|element| {
+                        let mut element = Element::from(element);
+                        element.transform(Transform::Translate(10, 10));
+                        element
+                    })
+                    .collect();
+                // 粘贴的标签和箭头绑定到一起粘贴的元素上
+                let ids: HashMap<ElementId, ElementId> = elements
+                    .iter()
+                    .zip(pasted.iter())
+                    .map(|(element, copy)| (element.id.clone(), copy.id.clone()))
+                    .collect();
+                let remap =
+                    |id: &Option<ElementId>| id.as_ref().and_then(|id| ids.get(id).cloned());
+                pasted.iter_mut().for_each(|element| {
+                    element.container_id = remap(&element.container_id);
+                    element.start_binding = remap(&element.start_binding);
+                    element.end_binding = remap(&element.end_binding);
                 });
-                app.draw();
+                pasted
+                    .into_iter()
+                    .for_each(|element| app.add_element(element));
+                app.end_change();
+                app.draw(&app_state_cloned.viewport.get());
+                app.save_to_local_storage();
             }
         };
         add_event_listener("paste", on_paste);
@@ -102,7 +181,21 @@ fn App<'a, G: Html>(ctx: Scope<'a>) -> View<G> {
         let window = web_sys::window().expect("should have a window in this context");
         let document = window.document().expect("should have a document on window");
         let (dragging, _, _) = *is_dragging.get();
-        if dragging {
+        let handle = match resizing.get().as_ref() {
+            Some((_, handle, _)) => Some(*handle),
+            None => *hover_handle.get(),
+        };
+        if panning.get().is_some() {
+            document
+                .document_element()
+                .unwrap()
+                .set_class_name("cursor-grabbing");
+        } else if let Some(handle) = handle {
+            document
+                .document_element()
+                .unwrap()
+                .set_class_name(handle.cursor());
+        } else if dragging {
             document
                 .document_element()
                 .unwrap()
@@ -117,153 +210,230 @@ fn App<'a, G: Html>(ctx: Scope<'a>) -> View<G> {
 
     view! (ctx,
         div {
+            FileTool()
             ExportTool()
             ConfigBar()
             Toolbar()
-            canvas(
-                ref=canvas_ref,
-                width=window_width,
-                height=window_height,
-                id="canvas",
-                style=format!("background-color: {}", app_state.view_bg_color.get()),
-                on:mousedown= move |event|  {
-                    let mouse_event = event.dyn_into::<MouseEvent>().unwrap();
-                    let x = mouse_event.offset_x();
-                    let y = mouse_event.offset_y();
-                    let selected_kind = *app_state.selected_kind.get();
-                    let mut app_data = app_state.get_data();
-                    let element = app_data.create_element(selected_kind, ElementConfig::new(app_state.item_stroke_color.to_string(),app_state.item_bg_color.to_string()));
+            // 文字编辑器相对画布定位
+            div(class="relative") {
+                canvas(
+                    ref=canvas_ref,
+                    // 画布的像素按像素比放大，显示的大小不变
+                    width=(app_state.canvas_size.get().0 * app_state.viewport.get().pixel_ratio).round(),
+                    height=(app_state.canvas_size.get().1 * app_state.viewport.get().pixel_ratio).round(),
+                    id="canvas",
+                    style=format!(
+                        "background-color: {}; width: {}px; height: {}px",
+                        app_state.view_bg_color.get(),
+                        app_state.canvas_size.get().0,
+                        app_state.canvas_size.get().1,
+                    ),
+                    on:mousedown= move |event|  {
+                        let mouse_event = event.dyn_into::<MouseEvent>().unwrap();
+                        if is_space_down.get() || mouse_event.button() == 1 {
+                            panning.set(Some((mouse_event.offset_x(), mouse_event.offset_y())));
+                            return;
+                        }
+                        let (x, y) = app_state.viewport.get().to_scene(mouse_event.offset_x(), mouse_event.offset_y());
+                        let selected_kind = *app_state.selected_kind.get();
 
-                    let id = element.id;
+                        if selected_kind == WidgetKind::Text {
+                            // 阻止画布获取焦点，否则编辑器会立即失去焦点
+                            mouse_event.prevent_default();
+                            app_state.edit_text_at(x, y);
+                            return;
+                        }
 
-                    if *app_state.selected_kind.get() == WidgetKind::Text {
-                        let (rect, text) = get_text_info(canvas_ref,x,y);
-                        if text == "" {
+                        let mut app_data = app_state.get_data();
+                        // 在画布外松开鼠标时收不到 mouseup，先结束上一次拖动的修改
+                        if app_data.history.is_recording() {
+                            app_data.history.commit_all();
+                            is_dragging.set((false, 0, 0));
+                            resizing.set(None);
+                        }
+                        app_data.begin_change();
+                        let config = ElementConfig::new(app_state.item_stroke_color.to_string(),app_state.item_bg_color.to_string());
+                        // tracing::info!("Mouse down at ({}, {})", x, y);
+
+                        // 如果当前是选择模式，且鼠标在某个元素上，则准备进入拖动模式
+                        if selected_kind == WidgetKind::Selection {
+                            let zoom = app_state.viewport.get().zoom;
+                            // 控制点在元素之上，优先于元素本身
+                            if let Some((id, handle)) = app_data.get_handle_by_point(x, y, zoom) {
+                                let origin = app_data.get_element(&id).unwrap().rect;
+                                resizing.set(Some((id, handle, origin)));
+                            } else if let Some(element) = app_data.get_element_by_point(x, y, zoom) {
+                                // 拖动未选中的元素时先选中它
+                                if !element.is_selected {
+                                    let id = element.id.clone();
+                                    app_data.select_element(&id, mouse_event.shift_key());
+                                }
+                                is_dragging.set((true, x, y));
+                            } else {
+                                app_data.set_selection_box(Some(Rect::new(x, y, x, y)));
+                            }
+                            drawing_state.set((None, x, y));
+                        } else {
+                            app_data.clean_selected_state(); // 清理当前的选中状态
+                            let id = app_data.create_element(selected_kind, config).id.clone();
+                            drawing_state.set((Some(id), x, y));
+                        }
+                    },
+                    on:mousemove= move |event| {
+                        let (id, start_x, start_y) = drawing_state.get().as_ref().clone();
+                        let (dragging, d_x, d_y) = *is_dragging.get();
+                        let mouse_event = event.dyn_into::<MouseEvent>().unwrap();
+                        if let Some((last_x, last_y)) = *panning.get() {
+                            let (screen_x, screen_y) = (mouse_event.offset_x(), mouse_event.offset_y());
+                            app_state.update_viewport(|viewport| {
+                                viewport.pan((screen_x - last_x).into(), (screen_y - last_y).into())
+                            });
+                            panning.set(Some((screen_x, screen_y)));
                             return;
                         }
-                        if let Some(element) = app_data.get_element_mut(id) {
-                            element.update_rect(rect.start_x, rect.start_y, rect.end_x, rect.end_y);
-                            element.update_shape_string(vec![text]);
-                            element.set_selected(true);
-                            app_data.draw();
-                            app_data.save_to_local_storage()
+                        let (x, y) = app_state.viewport.get().to_scene(mouse_event.offset_x(), mouse_event.offset_y());
+                        let mut app_data = app_state.get_data();
 
+                        if let Some((id, handle, origin)) = resizing.get().as_ref().clone() {
+                            if handle == Handle::Rotation {
+                                let angle = get_rotation(origin, x, y, mouse_event.shift_key());
+                                app_data.rotate_element(&id, angle);
+                            } else if let Some(element) = app_data.get_element(&id) {
+                                let rect = resize_rect(origin, element.angle, handle, x, y, mouse_event.shift_key(), mouse_event.alt_key());
+                                app_data.resize_element(&id, rect);
+                            }
+                            app_data.draw(&app_state.viewport.get());
+                            return;
                         }
-                        return;
-                    }
-                    // tracing::info!("Mouse down at ({}, {})", x, y);
 
-                    // 如果当前是选择模式，且鼠标在某个元素上，则准备进入拖动模式
-                    if *app_state.selected_kind.get() == WidgetKind::Selection {
-                        let point_in_some_element = app_data.get_element_by_point(x,y);
-                        if point_in_some_element.is_some() {
+                        // 如果是拖动选中的组件
+                        if dragging {
+                            let offset_x = x - d_x;
+                            let offset_y = y - d_y;
                             is_dragging.set((true, x, y));
+                            app_data.move_selected_elements(offset_x, offset_y);
+                            app_data.draw(&app_state.viewport.get());
+                            return;
                         }
-                    } else {
-                        app_data.clean_selected_state(); // 清理当前的选中状态
-                    }
-                    drawing_state.set((id, x, y));
-                },
-                on:mousemove= move |event| {
-                    let (id, start_x, start_y) = *drawing_state.get();
-                    let (dragging, d_x, d_y) = *is_dragging.get();
-                    let mouse_event = event.dyn_into::<MouseEvent>().unwrap();
-                    let x = mouse_event.offset_x();
-                    let y = mouse_event.offset_y();
-                    let mut app_data = app_state.get_data();
 
-                    // 如果是拖动选中的组件
-                    if dragging {
-                        let offset_x = x - d_x;
-                        let offset_y = y - d_y;
-                        is_dragging.set((true, x, y));
-                        app_data.move_selected_elements(offset_x, offset_y);
-                        app_data.draw();
-                        return;
-                    }
 
+                        if let Some(selection_box) = app_data.get_selection_box() {
+                            let rect = Rect::new(selection_box.start_x, selection_box.start_y, x, y);
+                            app_data.set_selection_box(Some(rect));
+                            app_data.select_elements(rect);
+                            app_data.draw(&app_state.viewport.get());
+                            return;
+                        }
 
-                    if id > 0.0 {
-                        let widget = create_widget(
-                            *app_state.selected_kind.get(),
-                            Rect::new(start_x, start_y, x, y),
-                            app_state.item_stroke_color.to_string(),
-                            app_state.item_bg_color.to_string()
-                        );
-                        let config_string = widget.get_config();
-                        let rect = Rect::new(start_x, start_y, x, y);
-                        if let Some(element) = app_data.get_element_mut(id) {
-                            element.update_rect(rect.start_x, rect.start_y, rect.end_x, rect.end_y);
-                            element.update_shape_string(config_string);
-                            if *app_state.selected_kind.get() == WidgetKind::Selection {
-                                app_data.select_elements(rect);
+                        if let Some(id) = id {
+                            let rect = Rect::new(start_x, start_y, x, y);
+                            if let Some(element) = app_data.get_element(&id) {
+                                let mut element = element.clone();
+                                let widget = create_widget(
+                                    *app_state.selected_kind.get(),
+                                    rect,
+                                    app_state.item_stroke_color.to_string(),
+                                    app_state.item_bg_color.to_string(),
+                                    element.seed,
+                                );
+                                element.update_rect(rect.start_x, rect.start_y, rect.end_x, rect.end_y);
+                                element.update_shapes(widget.get_config());
+                                app_data.update_element(element);
                             }
+                            app_data.draw(&app_state.viewport.get());
+                            return;
                         }
-                        app_data.draw();
-                        return;
-                    }
-                },
-                on:mouseup= move |event| {
-                    let (id, start_x, start_y) = *drawing_state.get();
-                    let mouse_event = event.dyn_into::<MouseEvent>().unwrap();
-                    let x = mouse_event.offset_x();
-                    let y = mouse_event.offset_y();
-                    let mut app_data = app_state.get_data();
-                    app_data.clean();
-                    let has_dragged = start_x != x || start_y != y;
 
-                    // 如果是在绘制图形，则在绘制完毕后选中该图形
-                    if *app_state.selected_kind.get() != WidgetKind::Selection {
-                        app_data.clean_selected_state();
-                        if let Some(element) = app_data.get_element_mut(id) {
-                            element.set_selected(true);
+                        if *app_state.selected_kind.get() == WidgetKind::Selection {
+                            let zoom = app_state.viewport.get().zoom;
+                            let handle = app_data.get_handle_by_point(x, y, zoom).map(|(_, handle)| handle);
+                            if *hover_handle.get() != handle {
+                                hover_handle.set(handle);
+                            }
                         }
-                    }
+                    },
+                    on:mouseup= move |event| {
+                        let (id, start_x, start_y) = drawing_state.get().as_ref().clone();
+                        let mouse_event = event.dyn_into::<MouseEvent>().unwrap();
+                        if panning.get().is_some() {
+                            panning.set(None);
+                            return;
+                        }
+                        let (x, y) = app_state.viewport.get().to_scene(mouse_event.offset_x(), mouse_event.offset_y());
+                        let mut app_data = app_state.get_data();
+                        app_data.clean();
+                        let has_dragged = start_x != x || start_y != y;
+                        let was_resizing = resizing.get().is_some();
 
-                    if !has_dragged && *app_state.selected_kind.get() == WidgetKind::Selection {
-                        if let Some(point_in_some_element) = app_data.get_element_by_point_mut(x,y) {
-                            let id = point_in_some_element.id;
-                            app_data.select_element(id, mouse_event.shift_key());
-                        } else {
-                            app_data.clean_selected_state();
+                        // 如果是在绘制图形，则在绘制完毕后选中该图形
+                        if *app_state.selected_kind.get() != WidgetKind::Selection {
+                            match id {
+                                Some(id) => {
+                                    // 箭头的两端绑定到所在的图形上
+                                    app_data.bind_arrow(&id, app_state.viewport.get().zoom);
+                                    app_data.select_element(&id, false);
+                                }
+                                None => app_data.clean_selected_state(),
+                            }
                         }
-                    }
 
-                    app_data.draw();
+                        if !has_dragged && !was_resizing && *app_state.selected_kind.get() == WidgetKind::Selection {
+                            let zoom = app_state.viewport.get().zoom;
+                            if let Some(point_in_some_element) = app_data.get_element_by_point(x, y, zoom) {
+                                let id = point_in_some_element.id.clone();
+                                app_data.select_element(&id, mouse_event.shift_key());
+                            } else {
+                                app_data.clean_selected_state();
+                            }
+                        }
 
-                    tracing::info!("Mouse up at ({}, {})", x, y);
-                    app_state.set_selected_kind_default();
-                    drawing_state.set((0.0, 0, 0));
-                    is_dragging.set((false, 0, 0));
-                    app_data.save_to_local_storage();
-                },
-            )
+                        app_data.end_change();
+                        app_data.draw(&app_state.viewport.get());
+
+                        tracing::info!("Mouse up at ({}, {})", x, y);
+                        app_state.set_selected_kind_default();
+                        drawing_state.set((None, 0, 0));
+                        is_dragging.set((false, 0, 0));
+                        resizing.set(None);
+                        app_data.save_to_local_storage();
+                    },
+                    on:dblclick= move |event| {
+                        let mouse_event = event.dyn_into::<MouseEvent>().unwrap();
+                        if *app_state.selected_kind.get() != WidgetKind::Selection {
+                            return;
+                        }
+                        let (x, y) = app_state.viewport.get().to_scene(mouse_event.offset_x(), mouse_event.offset_y());
+                        app_state.edit_text_at(x, y);
+                    },
+                    on:wheel= move |event| {
+                        let wheel_event = event.dyn_into::<WheelEvent>().unwrap();
+                        wheel_event.prevent_default();
+                        // 以行为单位滚动时（Firefox 的鼠标滚轮）换算为像素
+                        let unit = if wheel_event.delta_mode() == WheelEvent::DOM_DELTA_LINE { 16.0 } else { 1.0 };
+                        let delta_x = wheel_event.delta_x() * unit;
+                        let delta_y = wheel_event.delta_y() * unit;
+                        // 触控板的双指缩放会带上 ctrlKey
+                        if wheel_event.ctrl_key() || wheel_event.meta_key() {
+                            let x = wheel_event.offset_x().into();
+                            let y = wheel_event.offset_y().into();
+                            app_state.update_viewport(|viewport| {
+                                viewport.zoom_at(x, y, viewport.zoom * (-delta_y * 0.01).exp())
+                            });
+                        } else {
+                            app_state.update_viewport(|viewport| viewport.pan(-delta_x, -delta_y));
+                        }
+                    },
+                )
+                TextEditor()
+            }
         }
     )
 }
 
-pub fn get_text_info<G: Html>(canvas_ref: &NodeRef<G>, x: i32, y: i32) -> (Rect, String) {
-    let canvas: HtmlCanvasElement = canvas_ref.get::<DomNode>().unchecked_into();
+fn get_window_size() -> (f64, f64) {
     let window = web_sys::window().expect("should have a window in this context");
-    let ctx = canvas
-        .get_context("2d")
-        .expect("should get context")
-        .unwrap()
-        .dyn_into::<web_sys::CanvasRenderingContext2d>()
-        .expect("should cast to context");
-    let text = window
-        .prompt_with_message("What text do you want?")
-        .unwrap();
-    let text = text.unwrap();
-    let text_measure = ctx.measure_text(&text).unwrap();
-
-    let height = text_measure.font_bounding_box_ascent() + text_measure.font_bounding_box_descent();
-    let width = text_measure.width();
-    let rect = Rect {
-        start_x: x,
-        start_y: y,
-        end_x: x + width as i32,
-        end_y: y + height as i32,
-    };
-    (rect, text)
+    (
+        window.inner_width().unwrap().as_f64().unwrap(),
+        window.inner_height().unwrap().as_f64().unwrap(),
+    )
 }
@@ -0,0 +1,147 @@
+use crate::utils::geometry::rotate_point;
+
+use super::{element::Element, rect::Rect, transform::Transform, widget_kind::WidgetKind};
+
+/// Space left between the end of a bound arrow and the outline.
+pub const BINDING_GAP: f64 = 4.0;
+
+/// Whether arrows can be attached to the element.
+pub fn is_bindable(element: &Element) -> bool {
+    match element.kind {
+        WidgetKind::Rectangle | WidgetKind::Ellipse => true,
+        WidgetKind::Text => element.container_id.is_none(),
+        WidgetKind::Arrow | WidgetKind::Selection => false,
+    }
+}
+
+/**
+ * 点是否在元素内或者在它的边框附近，`margin` 为场景坐标
+ */
+pub fn is_point_in_element(element: &Element, x: i32, y: i32, margin: i32) -> bool {
+    let (x, y) = element.to_local_point(x, y);
+    let rect = element.rect.normalize();
+    x >= rect.start_x - margin
+        && x <= rect.end_x + margin
+        && y >= rect.start_y - margin
+        && y <= rect.end_y + margin
+}
+
+/**
+ * 箭头的两个端点在场景中的位置，包含箭头的旋转
+ */
+pub fn get_arrow_endpoints(arrow: &Element) -> ((f64, f64), (f64, f64)) {
+    let rect = arrow.rect;
+    let (center_x, center_y) = rect.get_center();
+    let rotate = |x: i32, y: i32| rotate_point(x.into(), y.into(), center_x, center_y, arrow.angle);
+    (
+        rotate(rect.start_x, rect.start_y),
+        rotate(rect.end_x, rect.end_y),
+    )
+}
+
+/**
+ * 从元素中心指向 (x, y) 的射线与元素边框的交点，再向外留出一点间隙。
+ * (x, y) 与中心重合时没有方向，返回 `None`
+ */
+pub fn get_binding_point(element: &Element, x: f64, y: f64) -> Option<(f64, f64)> {
+    let rect = element.rect.normalize();
+    let (center_x, center_y) = rect.get_center();
+    let (x, y) = rotate_point(x, y, center_x, center_y, -element.angle);
+    let (dx, dy) = (x - center_x, y - center_y);
+    let length = dx.hypot(dy);
+    if length == 0.0 {
+        return None;
+    }
+    let half_width = rect.get_width() as f64 / 2.0;
+    let half_height = rect.get_height() as f64 / 2.0;
+    // 沿方向 (dx, dy) 走到边框时的比例
+    let scale = match element.kind {
+        WidgetKind::Ellipse if half_width > 0.0 && half_height > 0.0 => {
+            1.0 / ((dx / half_width).powi(2) + (dy / half_height).powi(2)).sqrt()
+        }
+        _ => (half_width / dx.abs()).min(half_height / dy.abs()),
+    };
+    let scale = scale + BINDING_GAP / length;
+    let (x, y) = (center_x + dx * scale, center_y + dy * scale);
+    Some(rotate_point(x, y, center_x, center_y, element.angle))
+}
+
+/**
+ * 将箭头的端点贴到绑定的元素上：两端都指向对方（绑定的元素的中心或者未绑定的端点）。
+ * 箭头的角度归零，端点直接使用场景坐标
+ */
+pub fn snap_arrow(arrow: &mut Element, start: Option<&Element>, end: Option<&Element>) {
+    let ((start_x, start_y), (end_x, end_y)) = get_arrow_endpoints(arrow);
+    let start_target = end
+        .map(|end| end.rect.get_center())
+        .unwrap_or((end_x, end_y));
+    let end_target = start
+        .map(|start| start.rect.get_center())
+        .unwrap_or((start_x, start_y));
+    let (start_x, start_y) = start
+        .and_then(|start| get_binding_point(start, start_target.0, start_target.1))
+        .unwrap_or((start_x, start_y));
+    let (end_x, end_y) = end
+        .and_then(|end| get_binding_point(end, end_target.0, end_target.1))
+        .unwrap_or((end_x, end_y));
+    arrow.angle = 0.0;
+    arrow.transform(Transform::Resize(Rect::new(
+        start_x.round() as i32,
+        start_y.round() as i32,
+        end_x.round() as i32,
+        end_y.round() as i32,
+    )));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::element::ElementConfig;
+
+    fn create_element(kind: WidgetKind, rect: Rect) -> Element {
+        let mut element = Element::new(kind, ElementConfig::default());
+        element.transform(Transform::Resize(rect));
+        element
+    }
+
+    #[test]
+    fn labels_and_arrows_are_not_bindable() {
+        let rect = Rect::new(0, 0, 10, 10);
+        assert!(is_bindable(&create_element(WidgetKind::Rectangle, rect)));
+        assert!(is_bindable(&create_element(WidgetKind::Text, rect)));
+        assert!(!is_bindable(&create_element(WidgetKind::Arrow, rect)));
+        let mut label = create_element(WidgetKind::Text, rect);
+        label.container_id = Some(create_element(WidgetKind::Rectangle, rect).id);
+        assert!(!is_bindable(&label));
+    }
+
+    #[test]
+    fn binding_point_is_on_the_outline_plus_gap() {
+        let rect = Rect::new(0, 0, 100, 100);
+        for kind in [WidgetKind::Rectangle, WidgetKind::Ellipse] {
+            let element = create_element(kind, rect);
+            assert_eq!(
+                get_binding_point(&element, 200.0, 50.0),
+                Some((100.0 + BINDING_GAP, 50.0))
+            );
+            assert_eq!(get_binding_point(&element, 50.0, 50.0), None);
+        }
+        let corner = get_binding_point(&create_element(WidgetKind::Rectangle, rect), 0.0, 0.0);
+        let (x, y) = corner.unwrap();
+        assert!(x < 0.0 && (x - y).abs() < 1e-9);
+    }
+
+    #[test]
+    fn snapped_arrow_points_between_the_centers() {
+        let start = create_element(WidgetKind::Rectangle, Rect::new(0, 0, 100, 100));
+        let end = create_element(WidgetKind::Ellipse, Rect::new(200, 0, 300, 100));
+        let mut arrow = create_element(WidgetKind::Arrow, Rect::new(10, 90, 290, 10));
+        snap_arrow(&mut arrow, Some(&start), Some(&end));
+        assert_eq!(arrow.rect, Rect::new(104, 50, 196, 50));
+
+        // 只绑定起点时，终点保持不动
+        let mut arrow = create_element(WidgetKind::Arrow, Rect::new(50, 50, 250, 50));
+        snap_arrow(&mut arrow, Some(&start), None);
+        assert_eq!(arrow.rect, Rect::new(104, 50, 250, 50));
+    }
+}
@@ -0,0 +1,69 @@
+use std::f64::consts::SQRT_2;
+
+use crate::text_layout::{measure_text, wrap_text};
+
+use super::{
+    element::{Element, ElementConfig},
+    rect::Rect,
+    widget_kind::WidgetKind,
+};
+
+/// Space between the outline of a container and its label.
+const BOUND_TEXT_PADDING: f64 = 5.0;
+
+/// Whether the element can hold a label.
+pub fn is_container(element: &Element) -> bool {
+    matches!(element.kind, WidgetKind::Rectangle | WidgetKind::Ellipse)
+}
+
+/**
+ * 标签可以使用的最大宽度。椭圆使用内接矩形的宽度
+ */
+pub fn get_max_text_width(container: &Element) -> f64 {
+    let width = container.rect.get_width().abs() as f64;
+    let width = match container.kind {
+        WidgetKind::Ellipse => width / SQRT_2,
+        _ => width,
+    };
+    (width - BOUND_TEXT_PADDING * 2.0).max(0.0)
+}
+
+/**
+ * 标签在容器中的区域：按容器的宽度换行后居中
+ */
+pub fn get_bound_text_rect(text: &str, config: &ElementConfig, container: &Element) -> Rect {
+    get_bound_text_layout(text, config, container).1
+}
+
+/**
+ * 换行后的文字和它在容器中的区域
+ */
+fn get_bound_text_layout(
+    text: &str,
+    config: &ElementConfig,
+    container: &Element,
+) -> (String, Rect) {
+    let wrapped = wrap_text(text, config, get_max_text_width(container));
+    let (width, height) = measure_text(&wrapped, config);
+    let (center_x, center_y) = container.rect.get_center();
+    let start_x = (center_x - width / 2.0).round() as i32;
+    let start_y = (center_y - height / 2.0).round() as i32;
+    let rect = Rect::new(
+        start_x,
+        start_y,
+        start_x + width.ceil() as i32,
+        start_y + height.ceil() as i32,
+    );
+    (wrapped, rect)
+}
+
+/**
+ * 按容器重新排列标签，标签与容器一起旋转。换行的结果保存在标签上，绘制时直接使用
+ */
+pub fn layout_bound_text(label: &mut Element, container: &Element) {
+    let text = label.get_text().unwrap_or_default();
+    let (wrapped, rect) = get_bound_text_layout(text, &label.config, container);
+    label.rect = rect;
+    label.wrapped_text = Some(wrapped);
+    label.angle = container.angle;
+}
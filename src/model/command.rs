@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+use super::{element::Element, id::ElementId};
+
+/// A mutation of `AppData::elements`. Every change to the element list goes
+/// through `AppData::apply`, which returns the command that reverts it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Command {
+    AddElements(Vec<Element>),
+    DeleteElements(Vec<ElementId>),
+    /// Puts deleted elements back at their former index.
+    RestoreElements(Vec<(usize, Element)>),
+    MoveElements {
+        ids: Vec<ElementId>,
+        offset_x: i32,
+        offset_y: i32,
+    },
+    /// Replaces the element with the same id.
+    UpdateElement(Box<Element>),
+    /// Selects exactly these elements.
+    SetSelection(Vec<ElementId>),
+    Batch(Vec<Command>),
+}
+
+impl Command {
+    /// Whether the command changes the document rather than just the selection.
+    pub fn is_document_change(&self) -> bool {
+        match self {
+            Command::SetSelection(_) => false,
+            Command::Batch(commands) => commands.iter().any(Command::is_document_change),
+            Command::MoveElements {
+                ids,
+                offset_x,
+                offset_y,
+            } => !ids.is_empty() && (*offset_x != 0 || *offset_y != 0),
+            Command::AddElements(elements) => !elements.is_empty(),
+            Command::DeleteElements(ids) => !ids.is_empty(),
+            Command::RestoreElements(elements) => !elements.is_empty(),
+            Command::UpdateElement(_) => true,
+        }
+    }
+
+    /**
+     * 将同一次拖动产生的同一元素的逆操作合并，返回 false 表示无法合并
+     */
+    pub fn merge(&mut self, next: &Command) -> bool {
+        match (self, next) {
+            (
+                Command::MoveElements {
+                    ids,
+                    offset_x,
+                    offset_y,
+                },
+                Command::MoveElements {
+                    ids: next_ids,
+                    offset_x: next_x,
+                    offset_y: next_y,
+                },
+            ) if ids == next_ids => {
+                *offset_x += next_x;
+                *offset_y += next_y;
+                true
+            }
+            // the earlier inverse already holds the state before the drag
+            (Command::UpdateElement(element), Command::UpdateElement(next)) => {
+                element.id == next.id
+            }
+            (Command::SetSelection(_), Command::SetSelection(_)) => true,
+            _ => false,
+        }
+    }
+
+    /**
+     * `next` 能否越过这个更早的逆操作，与再之前的逆操作合并。
+     * 撤销时 `next` 在这个逆操作之前执行
+     */
+    pub fn can_merge_past(&self, next: &Command) -> bool {
+        match next {
+            // the earlier inverse restores the whole element, whatever happened
+            // in between, as long as the element still exists
+            Command::UpdateElement(element) => !self.changes_existence(&element.id),
+            Command::MoveElements { ids, .. } => match self {
+                Command::MoveElements { .. } | Command::SetSelection(_) => true,
+                _ => !ids.iter().any(|id| self.touches(id)),
+            },
+            Command::SetSelection(_) => true,
+            _ => false,
+        }
+    }
+
+    fn touches(&self, id: &ElementId) -> bool {
+        match self {
+            Command::MoveElements { ids, .. } => ids.contains(id),
+            Command::UpdateElement(element) => &element.id == id,
+            Command::SetSelection(_) => false,
+            Command::Batch(commands) => commands.iter().any(|command| command.touches(id)),
+            _ => self.changes_existence(id),
+        }
+    }
+
+    fn changes_existence(&self, id: &ElementId) -> bool {
+        match self {
+            Command::AddElements(elements) => elements.iter().any(|element| &element.id == id),
+            Command::DeleteElements(ids) => ids.contains(id),
+            Command::RestoreElements(elements) => {
+                elements.iter().any(|(_, element)| &element.id == id)
+            }
+            Command::Batch(commands) => {
+                commands.iter().any(|command| command.changes_existence(id))
+            }
+            _ => false,
+        }
+    }
+}
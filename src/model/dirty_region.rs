@@ -0,0 +1,21 @@
+use super::rect::Rect;
+
+/// The part of the scene that changed since it was last drawn.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum DirtyRegion {
+    /// Nothing is known about the canvas, so everything is redrawn.
+    #[default]
+    Full,
+    Region(Rect),
+    Clean,
+}
+
+impl DirtyRegion {
+    pub fn with(self, rect: Rect) -> DirtyRegion {
+        match self {
+            DirtyRegion::Full => DirtyRegion::Full,
+            DirtyRegion::Region(region) => DirtyRegion::Region(region.union(rect)),
+            DirtyRegion::Clean => DirtyRegion::Region(rect.normalize()),
+        }
+    }
+}
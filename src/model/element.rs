@@ -1,11 +1,36 @@
 use serde::{Deserialize, Serialize};
 
-use super::{rect::Rect, widget_kind::WidgetKind};
+use crate::{
+    utils::{geometry::rotate_point, random},
+    widget::create_widget,
+};
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+use super::{
+    font::{FontFamily, TextAlign, DEFAULT_FONT_SIZE, LINE_HEIGHT},
+    id::ElementId,
+    primitive::Primitive,
+    rect::Rect,
+    transform::Transform,
+    widget_kind::WidgetKind,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ElementConfig {
     pub item_stroke_color: String,
     pub item_bg_color: String,
+    /// Font of text elements, ignored by shapes.
+    #[serde(default)]
+    pub font_family: FontFamily,
+    #[serde(default = "default_font_size")]
+    pub font_size: f64,
+    #[serde(default)]
+    pub text_align: TextAlign,
+}
+
+impl Default for ElementConfig {
+    fn default() -> Self {
+        Self::new(String::new(), String::new())
+    }
 }
 
 impl ElementConfig {
@@ -13,30 +38,97 @@ impl ElementConfig {
         Self {
             item_stroke_color,
             item_bg_color,
+            font_family: FontFamily::default(),
+            font_size: DEFAULT_FONT_SIZE,
+            text_align: TextAlign::default(),
         }
     }
+
+    /// The css/canvas `font` shorthand, used for both measuring and drawing.
+    pub fn get_font(&self) -> String {
+        format!("{}px {}", self.font_size, self.font_family.css_name())
+    }
+
+    pub fn get_line_height(&self) -> f64 {
+        self.font_size * LINE_HEIGHT
+    }
+
+    /// Whether clicking inside the shape hits it, not just its outline.
+    pub fn is_filled(&self) -> bool {
+        !self.item_bg_color.is_empty() && self.item_bg_color != "transparent"
+    }
 }
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+/// An element is placed in the scene by `rect`, in world coordinates, and
+/// rotated by `angle` around the center of `rect`. `shapes` hold the local
+/// geometry: paths relative to `origin`, before rotation. Change placement
+/// through `transform` so both stay in sync.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Element {
-    pub id: f64,
+    pub id: ElementId,
     pub is_selected: bool,
     pub kind: WidgetKind,
     pub rect: Rect,
+    #[serde(default)]
+    pub shapes: Vec<Primitive>,
+    /// Shapes saved by older versions: serialized rough.js drawables, or the
+    /// text of a text element. Only read by `migrate`, never saved.
+    #[serde(default, skip_serializing)]
     pub shape_string: Vec<String>,
     pub config: ElementConfig,
+    /// Seed for the hand-drawn jitter, so regenerating the shape keeps its look.
+    #[serde(default)]
+    pub seed: u32,
+    /// Clockwise rotation around the center of `rect`, in radians.
+    #[serde(default)]
+    pub angle: f64,
+    /// Bumped whenever `shapes` change. Moving or rotating an element
+    /// doesn't change its local geometry and keeps the version.
+    #[serde(default)]
+    pub version: u32,
+    /// Random number changed together with `version`, so two edits that end
+    /// up with the same version number can still be told apart.
+    #[serde(default)]
+    pub version_nonce: u32,
+    /// The rectangle or ellipse a text element is the label of. The label
+    /// is laid out inside it and follows it around.
+    #[serde(default)]
+    pub container_id: Option<ElementId>,
+    /// The elements an arrow's start and end are attached to. Bound ends
+    /// stick to the outline of the element when it moves or resizes.
+    #[serde(default)]
+    pub start_binding: Option<ElementId>,
+    #[serde(default)]
+    pub end_binding: Option<ElementId>,
+    /// The text of a label wrapped to its container, set by
+    /// `layout_bound_text` so drawing doesn't measure the text again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wrapped_text: Option<String>,
+    /// Properties of an element opened from a `.excalidraw` file that this
+    /// app doesn't use, such as its stroke width or groups. Saved back as is.
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub scene_properties: serde_json::Map<String, serde_json::Value>,
 }
 
 impl Element {
     pub fn new(kind: WidgetKind, config: ElementConfig) -> Self {
-        let id = js_sys::Date::new_0().get_time();
         Self {
-            id: id,
+            id: ElementId::new(),
             is_selected: false,
-            kind: kind,
+            kind,
             rect: Rect::default(),
+            shapes: Vec::new(),
             shape_string: Vec::new(),
             config,
+            seed: random_seed(),
+            angle: 0.0,
+            version: 1,
+            version_nonce: (random::next_u64() >> 32) as u32,
+            container_id: None,
+            start_binding: None,
+            end_binding: None,
+            wrapped_text: None,
+            scene_properties: serde_json::Map::new(),
         }
     }
 
@@ -44,8 +136,74 @@ impl Element {
         self.rect = Rect::new(x1, y1, x2, y2);
     }
 
-    pub fn update_shape_string(&mut self, shape_string: Vec<String>) {
-        self.shape_string = shape_string;
+    pub fn update_shapes(&mut self, shapes: Vec<Primitive>) {
+        self.shapes = shapes;
+        self.bump_version();
+    }
+
+    pub fn bump_version(&mut self) {
+        self.version = self.version.wrapping_add(1);
+        self.version_nonce = (random::next_u64() >> 32) as u32;
+    }
+
+    /// Draws a new seed, so the element no longer jitters like the one it was
+    /// copied from.
+    pub fn reseed(&mut self) {
+        self.seed = random_seed();
+        self.regenerate_shapes();
+    }
+
+    /**
+     * 升级旧版本保存的元素：没有种子的元素重新生成种子，
+     * 文字元素的文字从 `shape_string` 中取出，其它元素重新生成形状
+     */
+    pub fn migrate(&mut self) {
+        let shape_string = std::mem::take(&mut self.shape_string);
+        // elements saved before they had a seed all share seed 0, and the same jitter
+        let reseed = self.seed == 0;
+        if reseed {
+            self.seed = random_seed();
+        }
+        if self.kind == WidgetKind::Text {
+            if let (None, Some(text)) = (self.get_text(), shape_string.into_iter().next()) {
+                self.shapes = vec![Primitive::Text { text }];
+            }
+        } else if reseed || self.shapes.is_empty() {
+            self.regenerate_shapes();
+        }
+    }
+
+    /// Rebuilds the hand-drawn shapes from `rect`, `config` and `seed`.
+    pub fn regenerate_shapes(&mut self) {
+        if self.kind == WidgetKind::Text || self.kind == WidgetKind::Selection {
+            return;
+        }
+        let widget = create_widget(
+            self.kind,
+            self.rect,
+            self.config.item_stroke_color.clone(),
+            self.config.item_bg_color.clone(),
+            self.seed,
+        );
+        self.update_shapes(widget.get_config());
+    }
+
+    pub fn get_text(&self) -> Option<&str> {
+        self.shapes.iter().find_map(|shape| match shape {
+            Primitive::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Width of the widest stroke, 1 for elements without strokes.
+    pub fn get_stroke_width(&self) -> f64 {
+        self.shapes
+            .iter()
+            .filter_map(|shape| match shape {
+                Primitive::Stroke { width, .. } => Some(*width),
+                _ => None,
+            })
+            .fold(1.0, f64::max)
     }
 
     pub fn set_selected(&mut self, is_selected: bool) {
@@ -56,24 +214,260 @@ impl Element {
         self.is_selected
     }
 
-    pub fn move_element(&mut self, x: i32, y: i32) {
-        self.rect = Rect::new(
-            self.rect.start_x + x,
-            self.rect.start_y + y,
-            self.rect.end_x + x,
-            self.rect.end_y + y,
-        );
+    /// Where the local coordinate system of `shapes` starts in the scene.
+    pub fn origin(&self) -> (i32, i32) {
+        (self.rect.start_x, self.rect.start_y)
+    }
+
+    /**
+     * 移动、缩放、旋转和翻转都经过这里。移动和旋转不改变局部的几何形状，
+     * 缩放和翻转后重新生成
+     */
+    pub fn transform(&mut self, transform: Transform) {
+        let rect = self.rect;
+        match transform {
+            Transform::Translate(x, y) => {
+                self.rect = Rect::new(
+                    rect.start_x + x,
+                    rect.start_y + y,
+                    rect.end_x + x,
+                    rect.end_y + y,
+                );
+            }
+            Transform::Resize(rect) => {
+                self.rect = rect;
+                self.regenerate_shapes();
+            }
+            Transform::Rotate(angle) => self.angle = angle,
+            Transform::FlipHorizontal(axis) => {
+                let mirror = |x: i32| (axis * 2.0 - x as f64).round() as i32;
+                self.rect = self.keep_orientation(Rect::new(
+                    mirror(rect.start_x),
+                    rect.start_y,
+                    mirror(rect.end_x),
+                    rect.end_y,
+                ));
+                self.angle = -self.angle;
+                self.regenerate_shapes();
+            }
+            Transform::FlipVertical(axis) => {
+                let mirror = |y: i32| (axis * 2.0 - y as f64).round() as i32;
+                self.rect = self.keep_orientation(Rect::new(
+                    rect.start_x,
+                    mirror(rect.start_y),
+                    rect.end_x,
+                    mirror(rect.end_y),
+                ));
+                self.angle = -self.angle;
+                self.regenerate_shapes();
+            }
+        }
+        if self.angle != 0.0 {
+            self.angle = self.angle.rem_euclid(std::f64::consts::TAU);
+        }
+    }
+
+    /// Arrows point from the start of `rect` to its end, so a mirrored rect
+    /// is kept as is. Other kinds are symmetric and keep a normalized rect.
+    fn keep_orientation(&self, rect: Rect) -> Rect {
+        if self.kind == WidgetKind::Arrow {
+            rect
+        } else {
+            rect.normalize()
+        }
     }
 
     pub fn from(element: &Element) -> Self {
-        let id = js_sys::Date::new_0().get_time();
-        Self {
-            id,
+        let mut copy = Self {
+            id: ElementId::new(),
             is_selected: element.is_selected,
-            kind: element.kind.clone(),
-            rect: element.rect.clone(),
-            shape_string: element.shape_string.clone(),
+            kind: element.kind,
+            rect: element.rect,
+            shapes: element.shapes.clone(),
+            shape_string: Vec::new(),
             config: element.config.clone(),
+            seed: element.seed,
+            angle: element.angle,
+            version: element.version,
+            version_nonce: element.version_nonce,
+            container_id: element.container_id.clone(),
+            start_binding: element.start_binding.clone(),
+            end_binding: element.end_binding.clone(),
+            wrapped_text: element.wrapped_text.clone(),
+            scene_properties: element.scene_properties.clone(),
+        };
+        copy.reseed();
+        copy
+    }
+
+    /**
+     * 将场景中的点转换到元素未旋转时的坐标系中，用于碰撞检测
+     */
+    pub fn to_local_point(&self, x: i32, y: i32) -> (i32, i32) {
+        if self.angle == 0.0 {
+            return (x, y);
+        }
+        let (center_x, center_y) = self.rect.get_center();
+        let (x, y) = rotate_point(x.into(), y.into(), center_x, center_y, -self.angle);
+        (x.round() as i32, y.round() as i32)
+    }
+
+    /// Axis-aligned box around the rotated element.
+    pub fn get_bounds(&self) -> Rect {
+        let rect = self.rect.normalize();
+        if self.angle == 0.0 {
+            return rect;
         }
+        let (center_x, center_y) = rect.get_center();
+        let corners: Vec<(f64, f64)> = if self.kind == WidgetKind::Arrow {
+            vec![
+                (self.rect.start_x.into(), self.rect.start_y.into()),
+                (self.rect.end_x.into(), self.rect.end_y.into()),
+            ]
+        } else {
+            vec![
+                (rect.start_x.into(), rect.start_y.into()),
+                (rect.end_x.into(), rect.start_y.into()),
+                (rect.end_x.into(), rect.end_y.into()),
+                (rect.start_x.into(), rect.end_y.into()),
+            ]
+        };
+        let (mut min_x, mut min_y) = (f64::MAX, f64::MAX);
+        let (mut max_x, mut max_y) = (f64::MIN, f64::MIN);
+        corners.into_iter().for_each(|(x, y)| {
+            let (x, y) = rotate_point(x, y, center_x, center_y, self.angle);
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        });
+        Rect::new(
+            min_x.floor() as i32,
+            min_y.floor() as i32,
+            max_x.ceil() as i32,
+            max_y.ceil() as i32,
+        )
+    }
+}
+
+/// Bounding box of all elements, `None` when there are none.
+pub fn get_bounds(elements: &[&Element]) -> Option<Rect> {
+    elements
+        .iter()
+        .map(|element| element.get_bounds())
+        .reduce(|bounds, rect| bounds.union(rect))
+}
+
+fn random_seed() -> u32 {
+    (random::next_u64() >> 33) as u32
+}
+
+fn default_font_size() -> f64 {
+    DEFAULT_FONT_SIZE
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_1_SQRT_2, PI};
+
+    use super::*;
+
+    fn rectangle() -> Element {
+        let mut element = Element::new(
+            WidgetKind::Rectangle,
+            ElementConfig::new("#000".into(), "transparent".into()),
+        );
+        element.transform(Transform::Resize(Rect::new(0, 0, 100, 60)));
+        element
+    }
+
+    /// The bounds are rounded outwards, so they hold the exact box and are at
+    /// most a pixel bigger.
+    fn assert_bounds(bounds: Rect, (min_x, min_y, max_x, max_y): (f64, f64, f64, f64)) {
+        let (start_x, start_y) = (bounds.start_x as f64, bounds.start_y as f64);
+        let (end_x, end_y) = (bounds.end_x as f64, bounds.end_y as f64);
+        assert!(start_x <= min_x && min_x - start_x <= 1.0, "{bounds:?}");
+        assert!(start_y <= min_y && min_y - start_y <= 1.0, "{bounds:?}");
+        assert!(end_x >= max_x && end_x - max_x <= 1.0, "{bounds:?}");
+        assert!(end_y >= max_y && end_y - max_y <= 1.0, "{bounds:?}");
+    }
+
+    #[test]
+    fn rotated_bounds_hold_every_corner() {
+        let mut element = rectangle();
+        assert_eq!(element.get_bounds(), Rect::new(0, 0, 100, 60));
+
+        element.transform(Transform::Rotate(PI / 2.0));
+        assert_bounds(element.get_bounds(), (20.0, -20.0, 80.0, 80.0));
+
+        // 旋转 45° 时四个角到中心的水平和垂直距离都是 (50 + 30) / √2
+        let half = 80.0 * FRAC_1_SQRT_2;
+        element.transform(Transform::Rotate(PI / 4.0));
+        assert_bounds(
+            element.get_bounds(),
+            (50.0 - half, 30.0 - half, 50.0 + half, 30.0 + half),
+        );
+    }
+
+    #[test]
+    fn rotated_arrow_bounds_hold_its_endpoints() {
+        let mut arrow = Element::new(WidgetKind::Arrow, ElementConfig::default());
+        arrow.transform(Transform::Resize(Rect::new(0, 0, 100, 60)));
+        arrow.transform(Transform::Rotate(PI / 4.0));
+        let half_width = 20.0 * FRAC_1_SQRT_2;
+        let half_height = 80.0 * FRAC_1_SQRT_2;
+        assert_bounds(
+            arrow.get_bounds(),
+            (
+                50.0 - half_width,
+                30.0 - half_height,
+                50.0 + half_width,
+                30.0 + half_height,
+            ),
+        );
+    }
+
+    #[test]
+    fn copies_get_their_own_jitter() {
+        let element = rectangle();
+        let copy = Element::from(&element);
+        assert_ne!(copy.id, element.id);
+        assert_ne!(copy.seed, element.seed);
+        assert_ne!(copy.shapes, element.shapes);
+        assert_eq!(copy.rect, element.rect);
+    }
+
+    #[test]
+    fn same_seed_same_shapes() {
+        let element = rectangle();
+        let mut regenerated = element.clone();
+        regenerated.regenerate_shapes();
+        assert_eq!(regenerated.shapes, element.shapes);
+    }
+
+    #[test]
+    fn migrates_legacy_shape_strings() {
+        let legacy = |kind: &str, shape: &str| {
+            let json = format!(
+                r##"{{"id": 1600000000000.0, "is_selected": false, "kind": "{kind}",
+                    "rect": {{"start_x": 10, "start_y": 10, "end_x": 110, "end_y": 60}},
+                    "shape_string": ["{shape}"],
+                    "config": {{"item_stroke_color": "#000", "item_bg_color": "transparent"}}}}"##
+            );
+            let mut element: Element = serde_json::from_str(&json).unwrap();
+            element.migrate();
+            element
+        };
+
+        let text = legacy("Text", "hello");
+        assert_eq!(text.get_text(), Some("hello"));
+        assert_ne!(text.seed, 0);
+
+        let rectangle = legacy("Rectangle", "{}");
+        assert!(rectangle.shape_string.is_empty());
+        assert!(!rectangle.shapes.is_empty());
+        assert_ne!(rectangle.seed, 0);
+        let saved = serde_json::to_value(&rectangle).unwrap();
+        assert!(saved.get("shape_string").is_none());
     }
 }
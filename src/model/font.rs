@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_FONT_SIZE: f64 = 20.0;
+/// The sizes offered in the config bar.
+pub const FONT_SIZES: [f64; 4] = [16.0, 20.0, 28.0, 36.0];
+/// Distance between the tops of two lines of text, relative to the font size.
+pub const LINE_HEIGHT: f64 = 1.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum FontFamily {
+    #[default]
+    HandDrawn,
+    Normal,
+    Code,
+}
+
+pub const FONT_FAMILIES: [FontFamily; 3] =
+    [FontFamily::HandDrawn, FontFamily::Normal, FontFamily::Code];
+
+impl FontFamily {
+    /// The css `font-family`, the same for the canvas, the text editor and svg.
+    pub fn css_name(&self) -> &'static str {
+        match self {
+            Self::HandDrawn => "Virgil, Segoe UI Emoji",
+            Self::Normal => "Helvetica, Segoe UI Emoji",
+            Self::Code => "Cascadia, Segoe UI Emoji, monospace",
+        }
+    }
+
+    /// The font's number in upstream Excalidraw's font list.
+    pub fn to_scene(&self) -> u32 {
+        match self {
+            Self::HandDrawn => 1,
+            Self::Normal => 2,
+            Self::Code => 3,
+        }
+    }
+
+    pub fn from_scene(id: u32) -> Self {
+        match id {
+            2 => Self::Normal,
+            3 => Self::Code,
+            _ => Self::HandDrawn,
+        }
+    }
+}
+
+impl std::fmt::Display for FontFamily {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::HandDrawn => write!(f, "Hand-drawn"),
+            Self::Normal => write!(f, "Normal"),
+            Self::Code => write!(f, "Code"),
+        }
+    }
+}
+
+/// Where the lines of a text element line up, within the width of its widest line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+pub const TEXT_ALIGNS: [TextAlign; 3] = [TextAlign::Left, TextAlign::Center, TextAlign::Right];
+
+impl TextAlign {
+    /// Value of the css and canvas `textAlign`, and of the `.excalidraw` format.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Left => "left",
+            Self::Center => "center",
+            Self::Right => "right",
+        }
+    }
+
+    pub fn from_name(value: &str) -> Self {
+        match value {
+            "center" => Self::Center,
+            "right" => Self::Right,
+            _ => Self::Left,
+        }
+    }
+}
@@ -0,0 +1,450 @@
+use std::f64::consts::PI;
+
+use crate::utils::geometry::rotate_point;
+
+use super::{element::Element, rect::Rect, widget_kind::WidgetKind};
+
+/// Gap between an element and its selection border.
+pub const SELECTION_MARGIN: i32 = 4;
+/// Size of a resize handle in screen pixels.
+const HANDLE_SIZE: f64 = 8.0;
+/// Distance of the rotation handle above the selection border in screen pixels.
+const ROTATION_HANDLE_OFFSET: f64 = 20.0;
+/// Step of the rotation while Shift is held.
+const ROTATION_SNAP: f64 = PI / 12.0;
+
+/// The eight resize handles around a selected element and the rotation
+/// handle above it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Handle {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+    Rotation,
+}
+
+pub const HANDLES: [Handle; 9] = [
+    Handle::N,
+    Handle::NE,
+    Handle::E,
+    Handle::SE,
+    Handle::S,
+    Handle::SW,
+    Handle::W,
+    Handle::NW,
+    Handle::Rotation,
+];
+
+impl Handle {
+    /// Which edges the handle moves: -1 for the left/top one, 1 for the
+    /// right/bottom one and 0 when it doesn't move along that axis.
+    fn direction(&self) -> (i32, i32) {
+        match self {
+            Handle::N => (0, -1),
+            Handle::NE => (1, -1),
+            Handle::E => (1, 0),
+            Handle::SE => (1, 1),
+            Handle::S => (0, 1),
+            Handle::SW => (-1, 1),
+            Handle::W => (-1, 0),
+            Handle::NW => (-1, -1),
+            Handle::Rotation => (0, 0),
+        }
+    }
+
+    /// Css class of the mouse cursor shown over the handle.
+    pub fn cursor(&self) -> &'static str {
+        match self {
+            Handle::N | Handle::S => "cursor-ns-resize",
+            Handle::E | Handle::W => "cursor-ew-resize",
+            Handle::NE | Handle::SW => "cursor-nesw-resize",
+            Handle::NW | Handle::SE => "cursor-nwse-resize",
+            Handle::Rotation => "cursor-grab",
+        }
+    }
+}
+
+/**
+ * 各个控制点在元素未旋转时的区域，控制点在屏幕上的大小不随缩放变化。
+ * 文字暂不支持缩放，只有旋转的控制点
+ */
+pub fn get_handles(element: &Element, zoom: f64) -> Vec<(Handle, Rect)> {
+    let rect = element.rect.normalize();
+    let left = rect.start_x - SELECTION_MARGIN;
+    let top = rect.start_y - SELECTION_MARGIN;
+    let right = rect.end_x + SELECTION_MARGIN;
+    let bottom = rect.end_y + SELECTION_MARGIN;
+    let half = (HANDLE_SIZE / zoom / 2.0).ceil() as i32;
+    HANDLES
+        .iter()
+        .filter(|handle| element.kind != WidgetKind::Text || **handle == Handle::Rotation)
+        .map(|handle| {
+            let (dx, dy) = handle.direction();
+            let x = match dx {
+                -1 => left,
+                1 => right,
+                _ => (left + right) / 2,
+            };
+            let y = match dy {
+                _ if *handle == Handle::Rotation => {
+                    top - (ROTATION_HANDLE_OFFSET / zoom).round() as i32
+                }
+                -1 => top,
+                1 => bottom,
+                _ => (top + bottom) / 2,
+            };
+            (*handle, Rect::new(x - half, y - half, x + half, y + half))
+        })
+        .collect()
+}
+
+pub fn get_handle_by_point(element: &Element, zoom: f64, x: i32, y: i32) -> Option<Handle> {
+    let (x, y) = element.to_local_point(x, y);
+    get_handles(element, zoom)
+        .into_iter()
+        .find(|(_, area)| {
+            x >= area.start_x && x <= area.end_x && y >= area.start_y && y <= area.end_y
+        })
+        .map(|(handle, _)| handle)
+}
+
+/**
+ * 拖动旋转控制点到 (x, y) 时元素的角度，`snap` 时按 15° 取整
+ */
+pub fn get_rotation(rect: Rect, x: i32, y: i32, snap: bool) -> f64 {
+    let (center_x, center_y) = rect.get_center();
+    // 控制点在正上方，此时角度为 0
+    let angle = (y as f64 - center_y).atan2(x as f64 - center_x) + PI / 2.0;
+    let angle = if snap {
+        (angle / ROTATION_SNAP).round() * ROTATION_SNAP
+    } else {
+        angle
+    };
+    angle.rem_euclid(PI * 2.0)
+}
+
+/**
+ * 拖动控制点到 (x, y) 后的区域，`origin` 是开始拖动时元素的区域，`angle` 是元素的角度。
+ * `keep_aspect` 保持宽高比，`from_center` 以中心为基准缩放。
+ * 返回的区域保持 `origin` 的方向，所以箭头不会因为缩放而调转。
+ */
+pub fn resize_rect(
+    origin: Rect,
+    angle: f64,
+    handle: Handle,
+    x: i32,
+    y: i32,
+    keep_aspect: bool,
+    from_center: bool,
+) -> Rect {
+    let rect = origin.normalize();
+    let (dx, dy) = handle.direction();
+    let (left, top) = (rect.start_x as f64, rect.start_y as f64);
+    let (right, bottom) = (rect.end_x as f64, rect.end_y as f64);
+    let (center_x, center_y) = ((left + right) / 2.0, (top + bottom) / 2.0);
+    // 在元素未旋转时的坐标系中计算
+    let (x, y) = rotate_point(x.into(), y.into(), center_x, center_y, -angle);
+
+    // 缩放的基准点和被拖动的边到基准点的原始距离
+    let anchor_x = match dx {
+        _ if from_center => center_x,
+        -1 => right,
+        _ => left,
+    };
+    let anchor_y = match dy {
+        _ if from_center => center_y,
+        -1 => bottom,
+        _ => top,
+    };
+    let origin_x = non_zero(if dx == -1 { left } else { right } - anchor_x);
+    let origin_y = non_zero(if dy == -1 { top } else { bottom } - anchor_y);
+    let mut scale_x = if dx == 0 {
+        1.0
+    } else {
+        (x - anchor_x) / origin_x
+    };
+    let mut scale_y = if dy == 0 {
+        1.0
+    } else {
+        (y - anchor_y) / origin_y
+    };
+
+    if keep_aspect && right > left && bottom > top {
+        match (dx, dy) {
+            (0, _) => scale_x = scale_y.abs(),
+            (_, 0) => scale_y = scale_x.abs(),
+            _ => {
+                let size = scale_x.abs().max(scale_y.abs());
+                scale_x = size.copysign(scale_x);
+                scale_y = size.copysign(scale_y);
+            }
+        }
+    }
+
+    // 不随控制点移动的方向以中心为基准缩放
+    let (x1, x2) = if dx == 0 {
+        let half = (right - left) / 2.0 * scale_x;
+        (center_x - half, center_x + half)
+    } else {
+        let moved = anchor_x + origin_x * scale_x;
+        let fixed = if from_center {
+            anchor_x - origin_x * scale_x
+        } else {
+            anchor_x
+        };
+        (fixed, moved)
+    };
+    let (y1, y2) = if dy == 0 {
+        let half = (bottom - top) / 2.0 * scale_y;
+        (center_y - half, center_y + half)
+    } else {
+        let moved = anchor_y + origin_y * scale_y;
+        let fixed = if from_center {
+            anchor_y - origin_y * scale_y
+        } else {
+            anchor_y
+        };
+        (fixed, moved)
+    };
+
+    // 元素绕新的中心旋转，平移使基准点在场景中的位置保持不变
+    let fixed_x = if dx == 0 { center_x } else { anchor_x };
+    let fixed_y = if dy == 0 { center_y } else { anchor_y };
+    let (before_x, before_y) = rotate_point(fixed_x, fixed_y, center_x, center_y, angle);
+    let (after_x, after_y) =
+        rotate_point(fixed_x, fixed_y, (x1 + x2) / 2.0, (y1 + y2) / 2.0, angle);
+    let (shift_x, shift_y) = (before_x - after_x, before_y - after_y);
+    let (x1, x2) = (x1 + shift_x, x2 + shift_x);
+    let (y1, y2) = (y1 + shift_y, y2 + shift_y);
+
+    let (x1, x2) = (x1.min(x2).round() as i32, x1.max(x2).round() as i32);
+    let (y1, y2) = (y1.min(y2).round() as i32, y1.max(y2).round() as i32);
+    let (start_x, end_x) = if origin.start_x <= origin.end_x {
+        (x1, x2)
+    } else {
+        (x2, x1)
+    };
+    let (start_y, end_y) = if origin.start_y <= origin.end_y {
+        (y1, y2)
+    } else {
+        (y2, y1)
+    };
+    Rect::new(start_x, start_y, end_x, end_y)
+}
+
+/// A flat element has no size to scale, so its edge just follows the mouse.
+fn non_zero(distance: f64) -> f64 {
+    if distance == 0.0 {
+        1.0
+    } else {
+        distance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{element::ElementConfig, transform::Transform};
+
+    const ORIGIN: Rect = Rect {
+        start_x: 0,
+        start_y: 0,
+        end_x: 100,
+        end_y: 50,
+    };
+
+    fn resize(handle: Handle, x: i32, y: i32) -> Rect {
+        resize_rect(ORIGIN, 0.0, handle, x, y, false, false)
+    }
+
+    fn create_element(kind: WidgetKind, rect: Rect, angle: f64) -> Element {
+        let mut element = Element::new(kind, ElementConfig::default());
+        element.transform(Transform::Resize(rect));
+        element.transform(Transform::Rotate(angle));
+        element
+    }
+
+    #[test]
+    fn rotation_is_measured_from_straight_up() {
+        let rect = Rect::new(0, 0, 100, 100);
+        let cases = [
+            ((50, 0), 0.0),
+            ((100, 50), PI / 2.0),
+            ((50, 100), PI),
+            ((0, 50), PI * 1.5),
+        ];
+        for ((x, y), expected) in cases {
+            assert!((get_rotation(rect, x, y, false) - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn shift_snaps_rotation_to_15_degrees() {
+        let rect = Rect::new(0, 0, 100, 100);
+        let degrees = |x, y, snap| get_rotation(rect, x, y, snap).to_degrees();
+        // 约 20° 和 23°
+        assert!((degrees(84, -44, false) - 19.88).abs() < 0.01);
+        assert!((degrees(84, -44, true) - 15.0).abs() < 1e-9);
+        assert!((degrees(89, -42, true) - 30.0).abs() < 1e-9);
+        // 略小于 360° 时取整为 0 而不是 360°
+        assert_eq!(degrees(49, 0, true), 0.0);
+        assert!((degrees(0, 50, true) - 270.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn handles_surround_the_selection_border() {
+        let element = create_element(WidgetKind::Rectangle, Rect::new(10, 20, 110, 70), 0.0);
+        let handles = get_handles(&element, 1.0);
+        assert_eq!(handles.len(), HANDLES.len());
+        let get_handle = |handle| handles.iter().find(|(h, _)| *h == handle).unwrap().1;
+        // 选中框为 (6, 16, 114, 74)，控制点为 8 像素
+        assert_eq!(get_handle(Handle::NW), Rect::new(2, 12, 10, 20));
+        assert_eq!(get_handle(Handle::N), Rect::new(56, 12, 64, 20));
+        assert_eq!(get_handle(Handle::E), Rect::new(110, 41, 118, 49));
+        assert_eq!(get_handle(Handle::SE), Rect::new(110, 70, 118, 78));
+        assert_eq!(get_handle(Handle::Rotation), Rect::new(56, -8, 64, 0));
+
+        // 放大后控制点在场景中变小，旋转控制点离得更近
+        let handles = get_handles(&element, 2.0);
+        let get_handle = |handle| handles.iter().find(|(h, _)| *h == handle).unwrap().1;
+        assert_eq!(get_handle(Handle::SE), Rect::new(112, 72, 116, 76));
+        assert_eq!(get_handle(Handle::Rotation), Rect::new(58, 4, 62, 8));
+    }
+
+    #[test]
+    fn text_only_has_the_rotation_handle() {
+        let text = create_element(WidgetKind::Text, Rect::new(0, 0, 100, 20), 0.0);
+        let handles: Vec<Handle> = get_handles(&text, 1.0)
+            .into_iter()
+            .map(|(handle, _)| handle)
+            .collect();
+        assert_eq!(handles, vec![Handle::Rotation]);
+    }
+
+    #[test]
+    fn handles_turn_with_the_element() {
+        let element = create_element(WidgetKind::Rectangle, Rect::new(0, 0, 100, 100), PI / 2.0);
+        // 元素坐标中 SE 控制点的中心 (104, 104) 顺时针旋转 90° 后在 (-4, 104)
+        assert_eq!(
+            get_handle_by_point(&element, 1.0, -4, 104),
+            Some(Handle::SE)
+        );
+        // 未旋转时 SE 的位置现在是 NE 控制点
+        assert_eq!(
+            get_handle_by_point(&element, 1.0, 104, 104),
+            Some(Handle::NE)
+        );
+        // 旋转控制点转到了右边
+        assert_eq!(
+            get_handle_by_point(&element, 1.0, 124, 50),
+            Some(Handle::Rotation)
+        );
+        assert_eq!(get_handle_by_point(&element, 1.0, 50, 50), None);
+    }
+
+    #[test]
+    fn each_handle_moves_its_own_edges() {
+        let cases = [
+            (Handle::N, (50, -10), Rect::new(0, -10, 100, 50)),
+            (Handle::NE, (120, -10), Rect::new(0, -10, 120, 50)),
+            (Handle::E, (150, 999), Rect::new(0, 0, 150, 50)),
+            (Handle::SE, (120, 80), Rect::new(0, 0, 120, 80)),
+            (Handle::S, (50, 80), Rect::new(0, 0, 100, 80)),
+            (Handle::SW, (-10, 80), Rect::new(-10, 0, 100, 80)),
+            (Handle::W, (-20, 999), Rect::new(-20, 0, 100, 50)),
+            (Handle::NW, (-10, -10), Rect::new(-10, -10, 100, 50)),
+        ];
+        for (handle, (x, y), expected) in cases {
+            assert_eq!(resize(handle, x, y), expected, "{handle:?}");
+        }
+    }
+
+    #[test]
+    fn dragging_past_the_anchor_flips() {
+        assert_eq!(resize(Handle::E, -50, 0), Rect::new(-50, 0, 0, 50));
+        assert_eq!(resize(Handle::N, 0, 80), Rect::new(0, 50, 100, 80));
+    }
+
+    #[test]
+    fn keep_aspect_scales_both_axes() {
+        let resize = |handle, x, y| resize_rect(ORIGIN, 0.0, handle, x, y, true, false);
+        assert_eq!(resize(Handle::SE, 200, 60), Rect::new(0, 0, 200, 100));
+        // 边上的控制点以另一个方向的中心为基准
+        assert_eq!(resize(Handle::E, 200, 0), Rect::new(0, -25, 200, 75));
+        assert_eq!(resize(Handle::N, 0, -50), Rect::new(-50, -50, 150, 50));
+    }
+
+    #[test]
+    fn from_center_keeps_the_center() {
+        let resize =
+            |handle, x, y, keep_aspect| resize_rect(ORIGIN, 0.0, handle, x, y, keep_aspect, true);
+        assert_eq!(resize(Handle::E, 150, 0, false), Rect::new(-50, 0, 150, 50));
+        assert_eq!(
+            resize(Handle::NW, -50, -25, false),
+            Rect::new(-50, -25, 150, 75)
+        );
+        assert_eq!(
+            resize(Handle::SE, 150, 25, true),
+            Rect::new(-50, -25, 150, 75)
+        );
+    }
+
+    #[test]
+    fn flipped_arrow_keeps_its_direction() {
+        let arrow = Rect::new(100, 50, 0, 0);
+        let resized = resize_rect(arrow, 0.0, Handle::E, 150, 0, false, false);
+        assert_eq!(resized, Rect::new(150, 50, 0, 0));
+    }
+
+    #[test]
+    fn rotated_element_keeps_the_opposite_corner() {
+        let origin = Rect::new(0, 0, 100, 60);
+        // 控制点拖到的位置（元素坐标）、不动的对角、缩放后的大小
+        let corners = [
+            (Handle::SE, (150.0, 90.0), (0.0, 0.0), (150, 90)),
+            (Handle::NW, (-40.0, -30.0), (1.0, 1.0), (140, 90)),
+            (Handle::NE, (130.0, -20.0), (0.0, 1.0), (130, 80)),
+            (Handle::SW, (-30.0, 80.0), (1.0, 0.0), (130, 80)),
+        ];
+        for angle in [0.3, PI / 2.0, 2.0, -1.0] {
+            for (handle, (local_x, local_y), (fixed_x, fixed_y), (width, height)) in corners {
+                let corner_of = |rect: Rect| {
+                    let rect = rect.normalize();
+                    let (center_x, center_y) = rect.get_center();
+                    let x = rect.start_x as f64 + rect.get_width() as f64 * fixed_x;
+                    let y = rect.start_y as f64 + rect.get_height() as f64 * fixed_y;
+                    rotate_point(x, y, center_x, center_y, angle)
+                };
+                let (center_x, center_y) = origin.get_center();
+                let (x, y) = rotate_point(local_x, local_y, center_x, center_y, angle);
+                let resized = resize_rect(
+                    origin,
+                    angle,
+                    handle,
+                    x.round() as i32,
+                    y.round() as i32,
+                    false,
+                    false,
+                );
+                let (before, after) = (corner_of(origin), corner_of(resized));
+                assert!(
+                    (before.0 - after.0).abs() <= 1.0 && (before.1 - after.1).abs() <= 1.0,
+                    "{handle:?} at {angle}: {before:?} moved to {after:?}"
+                );
+                assert!(
+                    (resized.get_width() - width).abs() <= 1,
+                    "{handle:?} at {angle}"
+                );
+                assert!(
+                    (resized.get_height() - height).abs() <= 1,
+                    "{handle:?} at {angle}"
+                );
+            }
+        }
+    }
+}
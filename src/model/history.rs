@@ -0,0 +1,249 @@
+use super::command::Command;
+
+const HISTORY_LIMIT: usize = 100;
+
+/// Undo and redo stacks of inverse commands. Commands recorded between
+/// `begin` and `commit` become a single entry. The pairs nest: a change made
+/// while a drag is recording joins the drag's entry.
+#[derive(Debug, Default, Clone)]
+pub struct History {
+    undo_stack: Vec<Command>,
+    redo_stack: Vec<Command>,
+    pending: Option<Vec<Command>>,
+    /// Number of `begin` calls not matched by a `commit` yet.
+    depth: usize,
+}
+
+impl History {
+    /**
+     * 开始记录，直到 `commit` 时才真正入栈，
+     * 这样一次连续的拖动只会产生一条记录
+     */
+    pub fn begin(&mut self) {
+        if self.depth == 0 {
+            self.pending = Some(Vec::new());
+        }
+        self.depth += 1;
+    }
+
+    pub fn record(&mut self, inverse: Command) {
+        match self.pending.as_mut() {
+            Some(pending) => record_pending(pending, inverse),
+            None => self.push(inverse),
+        }
+    }
+
+    /**
+     * 只有最外层的 `commit` 才入栈
+     */
+    pub fn commit(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        if self.depth > 0 {
+            return;
+        }
+        if let Some(mut pending) = self.pending.take() {
+            pending.reverse();
+            self.push(Command::Batch(pending));
+        }
+    }
+
+    /**
+     * 结束所有未结束的记录，用于没有收到对应 `commit` 的情况，
+     * 例如在画布外松开鼠标
+     */
+    pub fn commit_all(&mut self) {
+        if self.depth > 0 {
+            self.depth = 1;
+            self.commit();
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.depth > 0
+    }
+
+    pub fn take_undo(&mut self) -> Option<Command> {
+        self.commit_all();
+        self.undo_stack.pop()
+    }
+
+    pub fn take_redo(&mut self) -> Option<Command> {
+        self.commit_all();
+        self.redo_stack.pop()
+    }
+
+    pub fn push_undo(&mut self, inverse: Command) {
+        self.undo_stack.push(inverse);
+    }
+
+    pub fn push_redo(&mut self, inverse: Command) {
+        self.redo_stack.push(inverse);
+    }
+
+    fn push(&mut self, inverse: Command) {
+        if !inverse.is_document_change() {
+            return;
+        }
+        self.undo_stack.push(inverse);
+        if self.undo_stack.len() > HISTORY_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.redo_stack.clear();
+    }
+}
+
+/**
+ * 记录中的逆操作展开嵌套的 Batch，并与之前同一元素的逆操作合并，
+ * 这样一次拖动对每个元素只保留一条逆操作
+ */
+fn record_pending(pending: &mut Vec<Command>, inverse: Command) {
+    if let Command::Batch(commands) = inverse {
+        // a batch runs in order, `pending` is reversed on commit
+        commands
+            .into_iter()
+            .rev()
+            .for_each(|command| record_pending(pending, command));
+        return;
+    }
+    for earlier in pending.iter_mut().rev() {
+        if earlier.merge(&inverse) {
+            return;
+        }
+        if !earlier.can_merge_past(&inverse) {
+            break;
+        }
+    }
+    pending.push(inverse);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{
+        element::{Element, ElementConfig},
+        id::ElementId,
+        rect::Rect,
+        widget_kind::WidgetKind,
+    };
+
+    fn move_by(offset_x: i32) -> Command {
+        Command::MoveElements {
+            ids: vec![ElementId::from("a")],
+            offset_x,
+            offset_y: 0,
+        }
+    }
+
+    #[test]
+    fn nested_changes_make_one_entry() {
+        let mut history = History::default();
+        history.begin();
+        history.record(move_by(1));
+        history.begin();
+        history.record(Command::DeleteElements(vec![ElementId::from("b")]));
+        history.commit();
+        assert!(history.is_recording());
+        history.record(move_by(2));
+        history.commit();
+        assert!(!history.is_recording());
+        assert_eq!(history.undo_stack.len(), 1);
+    }
+
+    fn update(id: &str, x: i32) -> Command {
+        let mut element = Element::new(WidgetKind::Rectangle, ElementConfig::default());
+        element.id = ElementId::from(id);
+        element.rect = Rect::new(x, 0, x + 10, 10);
+        element.seed = 1;
+        element.version_nonce = 0;
+        Command::UpdateElement(Box::new(element))
+    }
+
+    fn move_ids(ids: &[&str], offset_x: i32) -> Command {
+        Command::MoveElements {
+            ids: ids.iter().map(|id| ElementId::from(*id)).collect(),
+            offset_x,
+            offset_y: 0,
+        }
+    }
+
+    fn get_pending(history: &History) -> &[Command] {
+        history.pending.as_deref().unwrap()
+    }
+
+    #[test]
+    fn dragging_a_bound_element_keeps_one_inverse_per_element() {
+        let mut history = History::default();
+        history.begin();
+        history.record(Command::SetSelection(vec![]));
+        for x in 0..50 {
+            // 移动容器和标签，再更新绑定的箭头
+            history.record(move_ids(&["rect", "label"], -1));
+            history.record(Command::Batch(vec![update("arrow", x)]));
+        }
+        assert_eq!(
+            get_pending(&history),
+            [
+                Command::SetSelection(vec![]),
+                move_ids(&["rect", "label"], -50),
+                update("arrow", 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn resizing_a_container_keeps_the_first_snapshots() {
+        let mut history = History::default();
+        history.begin();
+        for x in 0..50 {
+            history.record(update("rect", x));
+            history.record(update("label", x));
+            history.record(Command::Batch(vec![update("arrow", x), update("other", x)]));
+        }
+        assert_eq!(
+            get_pending(&history),
+            [
+                update("rect", 0),
+                update("label", 0),
+                update("other", 0),
+                update("arrow", 0),
+            ]
+        );
+        history.commit();
+        assert_eq!(history.undo_stack.len(), 1);
+    }
+
+    #[test]
+    fn merging_stops_at_changes_to_the_same_element() {
+        let mut history = History::default();
+        history.begin();
+        // 撤销时先恢复 a 再移动，两次移动不能合并
+        history.record(move_ids(&["a"], 1));
+        history.record(update("a", 0));
+        history.record(move_ids(&["a"], 2));
+        // b 被删除后又添加，之前的快照不能代替新的
+        history.record(update("b", 0));
+        history.record(Command::DeleteElements(vec![ElementId::from("b")]));
+        history.record(update("b", 5));
+        assert_eq!(get_pending(&history).len(), 6);
+    }
+
+    #[test]
+    fn unmatched_commit_is_ignored() {
+        let mut history = History::default();
+        history.commit();
+        history.record(move_by(1));
+        history.commit();
+        assert_eq!(history.undo_stack, [move_by(1)]);
+    }
+
+    #[test]
+    fn undo_keeps_the_open_change() {
+        let mut history = History::default();
+        history.begin();
+        history.begin();
+        history.record(move_by(1));
+        assert_eq!(history.take_undo(), Some(Command::Batch(vec![move_by(1)])));
+        assert!(!history.is_recording());
+        assert!(history.undo_stack.is_empty());
+    }
+}
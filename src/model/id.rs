@@ -0,0 +1,101 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::utils::random;
+
+const ALPHABET: &[u8; 64] = b"useandom-26T198340PX75pxJACKVERYMINDBUSHWOLF_GQZbfghjklqvwyzrict";
+const ID_LENGTH: usize = 21;
+
+/// A nanoid-style element id.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct ElementId(String);
+
+impl ElementId {
+    /// A fresh random id. There is no `Default`: an empty id could collide.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> ElementId {
+        let mut bits = 0u64;
+        let mut available = 0;
+        let id = (0..ID_LENGTH)
+            .map(|_| {
+                if available < 6 {
+                    bits = random::next_u64();
+                    available = 64;
+                }
+                let index = (bits & 63) as usize;
+                bits >>= 6;
+                available -= 6;
+                ALPHABET[index] as char
+            })
+            .collect();
+        ElementId(id)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for ElementId {
+    fn from(id: &str) -> Self {
+        ElementId(id.into())
+    }
+}
+
+impl std::fmt::Display for ElementId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for ElementId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // data saved by older versions uses the creation timestamp as id
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawId {
+            Text(String),
+            Timestamp(f64),
+        }
+        Ok(match RawId::deserialize(deserializer)? {
+            RawId::Text(id) => ElementId(id),
+            RawId::Timestamp(id) => ElementId(id.to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn ids_are_unique() {
+        let ids: HashSet<ElementId> = (0..10_000).map(|_| ElementId::new()).collect();
+        assert_eq!(ids.len(), 10_000);
+    }
+
+    #[test]
+    fn ids_use_the_alphabet() {
+        let id = ElementId::new();
+        assert_eq!(id.as_str().len(), ID_LENGTH);
+        assert!(id.as_str().bytes().all(|byte| ALPHABET.contains(&byte)));
+    }
+
+    #[test]
+    fn seeded_ids_repeat() {
+        random::set_seed(42);
+        let first: Vec<ElementId> = (0..3).map(|_| ElementId::new()).collect();
+        random::set_seed(42);
+        let second: Vec<ElementId> = (0..3).map(|_| ElementId::new()).collect();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn reads_legacy_timestamp_ids() {
+        let id: ElementId = serde_json::from_str("1600000000000.5").unwrap();
+        assert_eq!(id.as_str(), "1600000000000.5");
+        let id: ElementId = serde_json::from_str(r#""abc""#).unwrap();
+        assert_eq!(id, ElementId::from("abc"));
+    }
+}
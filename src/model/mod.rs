@@ -1,106 +1,794 @@
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+};
+
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::JsCast;
 
-use crate::{draw_scene::draw_scene, storage, utils::hit_test::hit_test};
+use crate::{
+    draw_scene::draw_scene,
+    storage,
+    text_layout::measure_text,
+    utils::hit_test::{hit_test, LINE_THRESHOLD},
+};
 
 use self::{
-    element::{Element, ElementConfig},
+    binding::{get_arrow_endpoints, is_bindable, is_point_in_element, snap_arrow},
+    bound_text::layout_bound_text,
+    command::Command,
+    dirty_region::DirtyRegion,
+    element::{get_bounds, Element, ElementConfig},
+    handle::{get_handle_by_point, Handle, SELECTION_MARGIN},
+    history::History,
+    id::ElementId,
     rect::Rect,
+    spatial_index::SpatialIndex,
+    transform::Transform,
+    viewport::Viewport,
     widget_kind::WidgetKind,
 };
 
+pub mod binding;
+pub mod bound_text;
+pub mod command;
+pub mod dirty_region;
 pub mod element;
+pub mod font;
+pub mod handle;
+pub mod history;
+pub mod id;
+pub mod primitive;
 pub mod rect;
+pub mod scene;
+pub mod spatial_index;
+pub mod transform;
+pub mod viewport;
 pub mod widget_kind;
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct AppData {
     pub elements: Vec<Element>,
+    #[serde(skip)]
+    pub history: History,
+    /// The rubber band drawn while selecting with the Selection tool, set
+    /// through `set_selection_box`.
+    #[serde(skip)]
+    selection_box: Option<Rect>,
+    /// Bounds of `elements`, kept in sync by `apply`.
+    #[serde(skip)]
+    pub index: SpatialIndex,
+    /// Where each element is in `elements`, kept in sync by `apply`.
+    #[serde(skip)]
+    positions: HashMap<ElementId, usize>,
+    /// The labels inside each container and the arrows attached to each
+    /// element, kept in sync by `apply`.
+    #[serde(skip)]
+    bound_elements: HashMap<ElementId, HashSet<ElementId>>,
+    /// What `draw` has to repaint, collected by `apply`.
+    #[serde(skip)]
+    dirty: Cell<DirtyRegion>,
+    /// The text element open in the text editor, hidden on the canvas so
+    /// it isn't drawn under the editor.
+    #[serde(skip)]
+    editing: Option<ElementId>,
 }
 
 impl AppData {
+    /**
+     * 所有对元素列表的修改都经过这里，返回可以撤销本次修改的命令
+     */
+    pub fn apply(&mut self, command: Command) -> Command {
+        match command {
+            Command::AddElements(elements) => {
+                let ids = elements.iter().map(|element| element.id.clone()).collect();
+                elements
+                    .iter()
+                    .for_each(|element| self.index_element(element));
+                let start = self.elements.len();
+                self.elements.extend(elements);
+                self.update_positions(start);
+                Command::DeleteElements(ids)
+            }
+            Command::DeleteElements(ids) => {
+                let targets: HashSet<&ElementId> = ids.iter().collect();
+                let mut removed = Vec::new();
+                let mut index = 0;
+                self.elements.retain(|element| {
+                    let keep = !targets.contains(&element.id);
+                    if !keep {
+                        removed.push((index, element.clone()));
+                    }
+                    index += 1;
+                    keep
+                });
+                removed
+                    .iter()
+                    .for_each(|(_, element)| self.unindex_element(element));
+                if let Some((start, _)) = removed.first() {
+                    self.update_positions(*start);
+                }
+                Command::RestoreElements(removed)
+            }
+            Command::RestoreElements(elements) => {
+                let ids = elements
+                    .iter()
+                    .map(|(_, element)| element.id.clone())
+                    .collect();
+                let mut start = self.elements.len();
+                elements.into_iter().for_each(|(index, element)| {
+                    self.index_element(&element);
+                    let index = index.min(self.elements.len());
+                    start = start.min(index);
+                    self.elements.insert(index, element);
+                });
+                self.update_positions(start);
+                Command::DeleteElements(ids)
+            }
+            Command::MoveElements {
+                ids,
+                offset_x,
+                offset_y,
+            } => {
+                let indices: HashSet<usize> = ids
+                    .iter()
+                    .filter_map(|id| self.positions.get(id).copied())
+                    .collect();
+                indices.into_iter().for_each(|index| {
+                    let element = &mut self.elements[index];
+                    element.transform(Transform::Translate(offset_x, offset_y));
+                    let (id, bounds) = (element.id.clone(), get_index_bounds(element));
+                    self.update_index(id, bounds);
+                });
+                Command::MoveElements {
+                    ids,
+                    offset_x: -offset_x,
+                    offset_y: -offset_y,
+                }
+            }
+            Command::UpdateElement(element) => match self.positions.get(&element.id).copied() {
+                Some(index) => {
+                    let targets = get_bound_targets(&self.elements[index]);
+                    self.unlink_element(&element.id, targets);
+                    self.index_element(&element);
+                    Command::UpdateElement(Box::new(std::mem::replace(
+                        &mut self.elements[index],
+                        *element,
+                    )))
+                }
+                None => Command::Batch(Vec::new()),
+            },
+            Command::SetSelection(ids) => {
+                let previous = self.get_selected_ids();
+                previous
+                    .iter()
+                    .chain(ids.iter())
+                    .for_each(|id| self.mark_element_dirty(id));
+                let targets: HashSet<&ElementId> = ids.iter().collect();
+                self.elements
+                    .iter_mut()
+                    .for_each(|element| element.set_selected(targets.contains(&element.id)));
+                Command::SetSelection(previous)
+            }
+            Command::Batch(commands) => {
+                let mut inverses: Vec<Command> = commands
+                    .into_iter()
+                    .map(|command| self.apply(command))
+                    .collect();
+                inverses.reverse();
+                Command::Batch(inverses)
+            }
+        }
+    }
+
+    fn index_element(&mut self, element: &Element) {
+        self.update_index(element.id.clone(), get_index_bounds(element));
+        get_bound_targets(element).into_iter().for_each(|target| {
+            self.bound_elements
+                .entry(target)
+                .or_default()
+                .insert(element.id.clone());
+        });
+    }
+
+    /**
+     * 更新元素在索引中的区域，变化前后的区域都需要重绘
+     */
+    fn update_index(&mut self, id: ElementId, bounds: Rect) {
+        self.mark_element_dirty(&id);
+        self.index.insert(id.clone(), bounds);
+        self.mark_element_dirty(&id);
+    }
+
+    fn unindex_element(&mut self, element: &Element) {
+        self.mark_element_dirty(&element.id);
+        self.index.remove(&element.id);
+        self.positions.remove(&element.id);
+        self.unlink_element(&element.id, get_bound_targets(element));
+    }
+
+    fn unlink_element(&mut self, id: &ElementId, targets: Vec<ElementId>) {
+        targets.iter().for_each(|target| {
+            if let Some(ids) = self.bound_elements.get_mut(target) {
+                ids.remove(id);
+                if ids.is_empty() {
+                    self.bound_elements.remove(target);
+                }
+            }
+        });
+    }
+
+    /// Records where the elements from `start` on are in `elements`.
+    fn update_positions(&mut self, start: usize) {
+        self.elements
+            .iter()
+            .enumerate()
+            .skip(start)
+            .for_each(|(index, element)| {
+                self.positions.insert(element.id.clone(), index);
+            });
+    }
+
+    /// Marks the element with its selection border as changed.
+    fn mark_element_dirty(&self, id: &ElementId) {
+        if let Some(bounds) = self.index.get_bounds(id) {
+            let margin = SELECTION_MARGIN * 2;
+            self.mark_dirty(Rect::new(
+                bounds.start_x - margin,
+                bounds.start_y - margin,
+                bounds.end_x + margin,
+                bounds.end_y + margin,
+            ));
+        }
+    }
+
+    pub fn mark_dirty(&self, rect: Rect) {
+        self.dirty.set(self.dirty.get().with(rect));
+    }
+
+    /**
+     * 画布整体变化时（平移、缩放、改变大小）需要全部重绘
+     */
+    pub fn mark_all_dirty(&self) {
+        self.dirty.set(DirtyRegion::Full);
+    }
+
+    /// Returns what changed since the last call and marks the scene clean.
+    pub fn take_dirty(&self) -> DirtyRegion {
+        self.dirty.replace(DirtyRegion::Clean)
+    }
+
+    pub fn get_selection_box(&self) -> Option<Rect> {
+        self.selection_box
+    }
+
+    pub fn set_selection_box(&mut self, selection_box: Option<Rect>) {
+        if let Some(rect) = self.selection_box {
+            self.mark_dirty(rect);
+        }
+        if let Some(rect) = selection_box {
+            self.mark_dirty(rect);
+        }
+        self.selection_box = selection_box;
+    }
+
+    pub fn get_editing(&self) -> Option<&ElementId> {
+        self.editing.as_ref()
+    }
+
+    pub fn set_editing(&mut self, id: Option<ElementId>) {
+        if let Some(id) = &self.editing {
+            self.mark_element_dirty(id);
+        }
+        if let Some(id) = &id {
+            self.mark_element_dirty(id);
+        }
+        self.editing = id;
+    }
+
+    pub fn rebuild_index(&mut self) {
+        self.mark_all_dirty();
+        self.index.clear();
+        self.positions.clear();
+        self.bound_elements.clear();
+        let elements = std::mem::take(&mut self.elements);
+        elements
+            .iter()
+            .for_each(|element| self.index_element(element));
+        self.elements = elements;
+        self.update_positions(0);
+    }
+
+    /**
+     * 与 `rect` 相交的元素，按绘制的顺序排列
+     */
+    pub fn get_elements_in_rect(&self, rect: Rect) -> Vec<&Element> {
+        self.get_elements_by_ids(self.index.query(rect).iter())
+    }
+
+    /// The elements with these ids in drawing order, unknown ids are skipped.
+    fn get_elements_by_ids<'a>(&self, ids: impl Iterator<Item = &'a ElementId>) -> Vec<&Element> {
+        let mut indices: Vec<usize> = ids
+            .filter_map(|id| self.positions.get(id).copied())
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+            .into_iter()
+            .map(|index| &self.elements[index])
+            .collect()
+    }
+
+    /**
+     * 执行命令并记录到撤销历史中
+     */
+    pub fn execute(&mut self, command: Command) {
+        let inverse = self.apply(command);
+        self.history.record(inverse);
+    }
+
     pub fn add_element(&mut self, element: Element) {
-        self.elements.push(element);
+        self.execute(Command::AddElements(vec![element]));
     }
 
     pub fn create_element(&mut self, kind: WidgetKind, config: ElementConfig) -> &Element {
         let element = Element::new(kind, config);
-        self.elements.push(element);
-        self.elements.last_mut().unwrap()
+        self.add_element(element);
+        self.elements.last().unwrap()
     }
 
-    pub fn get_element_mut(&mut self, id: f64) -> Option<&mut Element> {
-        self.elements.iter_mut().find(|e| e.id == id)
+    pub fn get_element(&self, id: &ElementId) -> Option<&Element> {
+        self.positions.get(id).map(|index| &self.elements[*index])
     }
 
-    pub fn get_element_by_point_mut(&mut self, x: i32, y: i32) -> Option<&mut Element> {
-        self.elements
-            .iter_mut()
-            .find(|element| hit_test(element, x, y))
+    pub fn update_element(&mut self, element: Element) {
+        self.execute(Command::UpdateElement(Box::new(element)));
     }
 
-    pub fn get_element_by_point(&self, x: i32, y: i32) -> Option<&Element> {
-        self.elements
+    /**
+     * 点中的最上层的元素，点选和拖动都使用它。点中标签时返回它的容器
+     */
+    pub fn get_element_by_point(&self, x: i32, y: i32, zoom: f64) -> Option<&Element> {
+        let margin = (LINE_THRESHOLD as f64 / zoom).ceil() as i32;
+        let element = self
+            .get_elements_in_rect(Rect::new(x - margin, y - margin, x + margin, y + margin))
+            .into_iter()
+            .rev()
+            .find(|element| hit_test(element, x, y, zoom))?;
+        match &element.container_id {
+            Some(id) => self.get_element(id).or(Some(element)),
+            None => Some(element),
+        }
+    }
+
+    /// The label inside a rectangle or ellipse.
+    pub fn get_bound_text(&self, container_id: &ElementId) -> Option<&Element> {
+        self.bound_elements
+            .get(container_id)?
+            .iter()
+            .filter_map(|id| self.get_element(id))
+            .find(|element| element.container_id.as_ref() == Some(container_id))
+    }
+
+    /**
+     * 元素以及它们的标签，标签跟随容器移动和删除
+     */
+    fn with_bound_texts(&self, mut ids: Vec<ElementId>) -> Vec<ElementId> {
+        let mut included: HashSet<ElementId> = ids.iter().cloned().collect();
+        let labels: Vec<ElementId> = ids
+            .iter()
+            .filter_map(|id| self.get_bound_text(id))
+            .map(|label| label.id.clone())
+            .filter(|id| included.insert(id.clone()))
+            .collect();
+        ids.extend(labels);
+        ids
+    }
+
+    /**
+     * 重新排列所有标签，用于字体加载完成或者打开文件之后。不记录到撤销历史中
+     */
+    pub fn layout_bound_texts(&mut self) {
+        let labels: Vec<Element> = self
+            .elements
             .iter()
-            .find(|element| element.rect.is_in_point(x, y))
+            .filter_map(|label| {
+                let container = self.get_element(label.container_id.as_ref()?)?;
+                let mut updated = label.clone();
+                layout_bound_text(&mut updated, container);
+                (updated != *label).then_some(updated)
+            })
+            .collect();
+        labels.into_iter().for_each(|label| {
+            self.apply(Command::UpdateElement(Box::new(label)));
+        });
+    }
+
+    /**
+     * 容器变化后重新排列它的标签
+     */
+    fn update_bound_text(&mut self, container_id: &ElementId) {
+        let container = match self.get_element(container_id) {
+            Some(container) => container,
+            None => return,
+        };
+        let label = match self.get_bound_text(container_id) {
+            Some(label) => label,
+            None => return,
+        };
+        let mut updated = label.clone();
+        layout_bound_text(&mut updated, container);
+        if updated != *label {
+            self.update_element(updated);
+        }
+    }
+
+    /**
+     * 画完箭头后，将两端绑定到端点所在的元素上，并贴到它们的边框
+     */
+    pub fn bind_arrow(&mut self, id: &ElementId, zoom: f64) {
+        let arrow = match self.get_element(id) {
+            Some(arrow) if arrow.kind == WidgetKind::Arrow => arrow,
+            _ => return,
+        };
+        let ((start_x, start_y), (end_x, end_y)) = get_arrow_endpoints(arrow);
+        let start = self.get_bindable_element_at(start_x, start_y, zoom, id);
+        let end = self
+            .get_bindable_element_at(end_x, end_y, zoom, id)
+            .filter(|end| start.as_ref() != Some(end));
+        if start.is_none() && end.is_none() {
+            return;
+        }
+        let mut arrow = arrow.clone();
+        arrow.start_binding = start;
+        arrow.end_binding = end;
+        self.snap_arrow(&mut arrow);
+        self.update_element(arrow);
+    }
+
+    /// The topmost element an arrow ending at (x, y) gets attached to.
+    fn get_bindable_element_at(
+        &self,
+        x: f64,
+        y: f64,
+        zoom: f64,
+        arrow_id: &ElementId,
+    ) -> Option<ElementId> {
+        let (x, y) = (x.round() as i32, y.round() as i32);
+        let margin = (LINE_THRESHOLD as f64 / zoom).ceil() as i32;
+        self.get_elements_in_rect(Rect::new(x - margin, y - margin, x + margin, y + margin))
+            .into_iter()
+            .rev()
+            .filter(|element| &element.id != arrow_id && is_bindable(element))
+            .find(|element| is_point_in_element(element, x, y, margin))
+            .map(|element| element.id.clone())
+    }
+
+    fn snap_arrow(&self, arrow: &mut Element) {
+        let start = arrow
+            .start_binding
+            .as_ref()
+            .and_then(|id| self.get_element(id));
+        let end = arrow
+            .end_binding
+            .as_ref()
+            .and_then(|id| self.get_element(id));
+        snap_arrow(arrow, start, end);
+    }
+
+    /**
+     * 元素变化后更新箭头的绑定：`ids` 中的箭头与不在 `ids` 中的元素解绑，
+     * 其它绑定到 `ids` 中元素的箭头重新贴到边框上
+     */
+    fn update_bindings(&mut self, ids: &[ElementId]) {
+        let changed: HashSet<&ElementId> = ids.iter().collect();
+        let is_changed =
+            |binding: &Option<ElementId>| binding.as_ref().is_some_and(|id| changed.contains(id));
+        // 变化的箭头，以及绑定到变化的元素上的箭头
+        let arrows = ids
+            .iter()
+            .filter_map(|id| self.bound_elements.get(id))
+            .flatten()
+            .chain(ids);
+        let commands: Vec<Command> = self
+            .get_elements_by_ids(arrows)
+            .into_iter()
+            .filter(|element| element.kind == WidgetKind::Arrow)
+            .filter_map(|arrow| {
+                let mut updated = arrow.clone();
+                if changed.contains(&arrow.id) {
+                    updated.start_binding = updated.start_binding.filter(|id| changed.contains(id));
+                    updated.end_binding = updated.end_binding.filter(|id| changed.contains(id));
+                } else if is_changed(&arrow.start_binding) || is_changed(&arrow.end_binding) {
+                    self.snap_arrow(&mut updated);
+                }
+                (updated != *arrow).then_some(Command::UpdateElement(Box::new(updated)))
+            })
+            .collect();
+        if !commands.is_empty() {
+            self.execute(Command::Batch(commands));
+        }
+    }
+
+    /**
+     * 只有选中单个元素时才显示缩放和旋转的控制点
+     */
+    pub fn get_transformable_element(&self) -> Option<&Element> {
+        match self.get_selected_elements()[..] {
+            [element] => Some(element),
+            _ => None,
+        }
+    }
+
+    pub fn get_handle_by_point(&self, x: i32, y: i32, zoom: f64) -> Option<(ElementId, Handle)> {
+        let element = self.get_transformable_element()?;
+        let handle = get_handle_by_point(element, zoom, x, y)?;
+        Some((element.id.clone(), handle))
+    }
+
+    pub fn resize_element(&mut self, id: &ElementId, rect: Rect) {
+        if let Some(element) = self.get_element(id) {
+            let mut element = element.clone();
+            element.transform(Transform::Resize(rect));
+            self.update_element(element);
+            self.update_bound_text(id);
+            self.update_bindings(std::slice::from_ref(id));
+        }
+    }
+
+    pub fn rotate_element(&mut self, id: &ElementId, angle: f64) {
+        if let Some(element) = self.get_element(id) {
+            let mut element = element.clone();
+            element.transform(Transform::Rotate(angle));
+            self.update_element(element);
+            self.update_bound_text(id);
+            self.update_bindings(std::slice::from_ref(id));
+        }
+    }
+
+    /**
+     * 以所有选中元素的边界中心为轴翻转
+     */
+    pub fn flip_selected_elements(&mut self, horizontal: bool) {
+        let selected = self.get_selected_elements();
+        let bounds = match get_bounds(&selected) {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        let (center_x, center_y) = bounds.get_center();
+        let transform = if horizontal {
+            Transform::FlipHorizontal(center_x)
+        } else {
+            Transform::FlipVertical(center_y)
+        };
+        let commands = selected
+            .into_iter()
+            .map(|element| {
+                let mut element = element.clone();
+                element.transform(transform);
+                Command::UpdateElement(Box::new(element))
+            })
+            .collect();
+        self.execute(Command::Batch(commands));
+        let ids = self.get_selected_ids();
+        ids.iter().for_each(|id| self.update_bound_text(id));
+        self.update_bindings(&ids);
+    }
+
+    /**
+     * 修改选中元素以及它们的标签的样式，并按新的样式重新生成形状
+     */
+    pub fn restyle_selected_elements(&mut self, restyle: impl Fn(&mut ElementConfig)) {
+        let mut resized = Vec::new();
+        let commands: Vec<Command> = self
+            .get_selected_elements_with_labels()
+            .into_iter()
+            .filter_map(|element| {
+                let mut updated = element.clone();
+                restyle(&mut updated.config);
+                if updated.config == element.config {
+                    return None;
+                }
+                if updated.kind == WidgetKind::Text {
+                    if updated.config.get_font() != element.config.get_font() {
+                        self.measure_restyled_text(&mut updated);
+                    }
+                    if updated.rect != element.rect && updated.container_id.is_none() {
+                        resized.push(updated.id.clone());
+                    }
+                    updated.bump_version();
+                } else {
+                    updated.regenerate_shapes();
+                }
+                Some(Command::UpdateElement(Box::new(updated)))
+            })
+            .collect();
+        if !commands.is_empty() {
+            self.execute(Command::Batch(commands));
+        }
+        if !resized.is_empty() {
+            self.update_bindings(&resized);
+        }
+    }
+
+    /**
+     * 字体变化后重新测量文字：标签在容器中重新排列，其它文字保持左上角不变
+     */
+    fn measure_restyled_text(&self, text: &mut Element) {
+        let container = text
+            .container_id
+            .as_ref()
+            .and_then(|id| self.get_element(id));
+        match container {
+            Some(container) => layout_bound_text(text, container),
+            None => {
+                let (width, height) =
+                    measure_text(text.get_text().unwrap_or_default(), &text.config);
+                let rect = text.rect.normalize();
+                text.rect = Rect::new(
+                    rect.start_x,
+                    rect.start_y,
+                    rect.start_x + width.ceil() as i32,
+                    rect.start_y + height.ceil() as i32,
+                );
+            }
+        }
     }
 
     pub fn select_elements(&mut self, rect: Rect) {
-        self.elements
-            .iter_mut()
-            .for_each(|element| element.set_selected(element.rect.is_inside(rect)));
+        let ids = self
+            .get_elements_in_rect(rect)
+            .into_iter()
+            .filter(|element| element.container_id.is_none())
+            .filter(|element| element.get_bounds().is_inside(rect))
+            .map(|element| element.id.clone())
+            .collect();
+        self.execute(Command::SetSelection(ids));
     }
 
     pub fn clean(&mut self) {
-        self.elements
-            .retain(|element| element.kind != WidgetKind::Selection);
+        self.set_selection_box(None);
     }
 
     pub fn clean_selected_state(&mut self) {
-        self.elements
-            .iter_mut()
-            .for_each(|element| element.set_selected(false));
-    }
-
-    pub fn select_element(&mut self, id: f64, add: bool) {
-        self.elements.iter_mut().for_each(|element| {
-            let need_select: bool = if add {
-                element.id == id || element.is_selected
-            } else {
-                element.id == id
-            };
-            element.set_selected(need_select)
-        });
+        self.execute(Command::SetSelection(Vec::new()));
+    }
+
+    pub fn select_element(&mut self, id: &ElementId, add: bool) {
+        let mut ids = if add {
+            self.get_selected_ids()
+        } else {
+            Vec::new()
+        };
+        ids.push(id.clone());
+        self.execute(Command::SetSelection(ids));
     }
 
     pub fn move_selected_elements(&mut self, offset_x: i32, offset_y: i32) {
-        self.elements
-            .iter_mut()
-            .filter(|element| element.is_selected)
-            .for_each(|element| element.move_element(offset_x, offset_y));
+        let ids = self.with_bound_texts(self.get_selected_ids());
+        self.execute(Command::MoveElements {
+            ids: ids.clone(),
+            offset_x,
+            offset_y,
+        });
+        self.update_bindings(&ids);
     }
 
     pub fn move_all_elements(&mut self, offset_x: i32, offset_y: i32) {
-        self.elements
-            .iter_mut()
-            .for_each(|element| element.move_element(offset_x, offset_y));
+        self.execute(Command::MoveElements {
+            ids: self
+                .elements
+                .iter()
+                .map(|element| element.id.clone())
+                .collect(),
+            offset_x,
+            offset_y,
+        });
+    }
+
+    pub fn delete_element(&mut self, id: &ElementId) {
+        self.delete_elements(vec![id.clone()]);
     }
 
     pub fn delete_selected_elements(&mut self) {
-        self.elements.retain(|element| !element.is_selected);
+        let ids = self.with_bound_texts(self.get_selected_ids());
+        self.delete_elements(ids);
+    }
+
+    /**
+     * 删除元素，同时解除箭头与它们的绑定，撤销时一起恢复
+     */
+    fn delete_elements(&mut self, ids: Vec<ElementId>) {
+        let deleted: HashSet<&ElementId> = ids.iter().collect();
+        let is_kept = |id: &ElementId| !deleted.contains(id);
+        let arrows = ids
+            .iter()
+            .filter_map(|id| self.bound_elements.get(id))
+            .flatten()
+            .filter(|id| is_kept(id));
+        let mut commands: Vec<Command> = self
+            .get_elements_by_ids(arrows)
+            .into_iter()
+            .filter(|element| element.kind == WidgetKind::Arrow)
+            .map(|arrow| {
+                let mut updated = arrow.clone();
+                updated.start_binding = updated.start_binding.filter(is_kept);
+                updated.end_binding = updated.end_binding.filter(is_kept);
+                Command::UpdateElement(Box::new(updated))
+            })
+            .collect();
+        commands.push(Command::DeleteElements(ids));
+        self.execute(Command::Batch(commands));
+    }
+
+    pub fn replace_elements(&mut self, elements: Vec<Element>) {
+        let ids = self
+            .elements
+            .iter()
+            .map(|element| element.id.clone())
+            .collect();
+        self.execute(Command::Batch(vec![
+            Command::DeleteElements(ids),
+            Command::AddElements(elements),
+        ]));
     }
 
     pub fn select_all_elements(&mut self) {
-        self.elements
-            .iter_mut()
-            .for_each(|element| element.set_selected(true));
+        let ids = self
+            .elements
+            .iter()
+            .filter(|element| element.container_id.is_none())
+            .map(|element| element.id.clone())
+            .collect();
+        self.execute(Command::SetSelection(ids));
     }
 
     pub fn get_selected_elements(&self) -> Vec<&Element> {
         self.elements.iter().filter(|e| e.is_selected).collect()
     }
 
-    pub fn draw(&self) {
+    /**
+     * 选中的元素以及它们的标签，复制和导出时使用
+     */
+    pub fn get_selected_elements_with_labels(&self) -> Vec<&Element> {
+        let ids = self.with_bound_texts(self.get_selected_ids());
+        self.get_elements_by_ids(ids.iter())
+    }
+
+    pub fn get_selected_ids(&self) -> Vec<ElementId> {
+        self.elements
+            .iter()
+            .filter(|e| e.is_selected)
+            .map(|e| e.id.clone())
+            .collect()
+    }
+
+    /**
+     * 在修改文档前调用，与 `end_change` 配对形成一条撤销记录
+     */
+    pub fn begin_change(&mut self) {
+        self.history.begin();
+    }
+
+    pub fn end_change(&mut self) {
+        self.history.commit();
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(inverse) = self.history.take_undo() {
+            let redo = self.apply(inverse);
+            self.history.push_redo(redo);
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(command) = self.history.take_redo() {
+            let undo = self.apply(command);
+            self.history.push_undo(undo);
+        }
+    }
+
+    pub fn draw(&self, viewport: &Viewport) {
         let window = web_sys::window().expect("no global `window` exists");
         let document = window.document().expect("should have a document on window");
         let main_canvas = document
@@ -108,15 +796,21 @@ impl AppData {
             .unwrap()
             .dyn_into::<web_sys::HtmlCanvasElement>()
             .expect("should cast to canvas");
-        draw_scene(main_canvas, self);
+        draw_scene(main_canvas, self, viewport);
     }
 
     pub fn get_from_local_storage() -> Self {
-        let mut app_data = match storage::read_data() {
-            Some(data) => data,
-            None => AppData::default(),
-        };
+        let mut app_data = storage::read_data().unwrap_or_default();
         app_data.clean_selected_state();
+        // data saved before ids were random may contain the same timestamp twice
+        let mut ids = HashSet::new();
+        app_data.elements.iter_mut().for_each(|element| {
+            if !ids.insert(element.id.clone()) {
+                element.id = ElementId::new();
+            }
+        });
+        app_data.elements.iter_mut().for_each(Element::migrate);
+        app_data.rebuild_index();
         app_data
     }
 
@@ -124,3 +818,290 @@ impl AppData {
         storage::save_data(self);
     }
 }
+
+/// The elements `element` is attached to, whose `bound_elements` list it.
+fn get_bound_targets(element: &Element) -> Vec<ElementId> {
+    element
+        .container_id
+        .iter()
+        .chain(&element.start_binding)
+        .chain(&element.end_binding)
+        .cloned()
+        .collect()
+}
+
+/// Bounds of the element including the part of its strokes outside `rect`.
+fn get_index_bounds(element: &Element) -> Rect {
+    let bounds = element.get_bounds();
+    let margin = element.get_stroke_width().ceil() as i32;
+    Rect::new(
+        bounds.start_x - margin,
+        bounds.start_y - margin,
+        bounds.end_x + margin,
+        bounds.end_y + margin,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use font::TextAlign;
+
+    fn add_shape(app_data: &mut AppData, kind: WidgetKind, rect: Rect) -> ElementId {
+        let mut element = Element::new(kind, ElementConfig::new("#000".into(), "#fff".into()));
+        element.transform(Transform::Resize(rect));
+        let id = element.id.clone();
+        app_data.add_element(element);
+        id
+    }
+
+    #[test]
+    fn restyle_is_one_undo_entry() {
+        let mut app_data = AppData::default();
+        let first = add_shape(
+            &mut app_data,
+            WidgetKind::Rectangle,
+            Rect::new(0, 0, 50, 50),
+        );
+        let second = add_shape(
+            &mut app_data,
+            WidgetKind::Ellipse,
+            Rect::new(100, 0, 150, 50),
+        );
+        app_data.select_all_elements();
+        let before = app_data.elements.clone();
+
+        app_data.begin_change();
+        app_data.restyle_selected_elements(|config| config.item_stroke_color = "#f00".into());
+        app_data.end_change();
+        for id in [&first, &second] {
+            let element = app_data.get_element(id).unwrap();
+            assert_eq!(element.config.item_stroke_color, "#f00");
+            assert!(element.shapes.iter().any(|shape| matches!(
+                shape,
+                primitive::Primitive::Stroke { color, .. } if color == "#f00"
+            )));
+        }
+
+        app_data.undo();
+        assert_eq!(app_data.elements, before);
+    }
+
+    #[test]
+    fn restyled_text_is_drawn_again() {
+        let mut app_data = AppData::default();
+        let mut text = Element::new(
+            WidgetKind::Text,
+            ElementConfig::new("#000".into(), "#fff".into()),
+        );
+        text.shapes = vec![primitive::Primitive::Text {
+            text: "hello".into(),
+        }];
+        text.transform(Transform::Resize(Rect::new(0, 0, 40, 20)));
+        let id = text.id.clone();
+        app_data.add_element(text);
+        app_data.select_all_elements();
+        let before = app_data.get_element(&id).unwrap().clone();
+
+        app_data.begin_change();
+        app_data.restyle_selected_elements(|config| config.text_align = TextAlign::Right);
+        app_data.end_change();
+        let text = app_data.get_element(&id).unwrap();
+        assert_eq!(text.config.text_align, TextAlign::Right);
+        assert_eq!(text.rect, before.rect);
+        assert_ne!(text.version, before.version);
+
+        app_data.undo();
+        assert_eq!(app_data.get_element(&id), Some(&before));
+    }
+
+    fn assert_positions(app_data: &AppData) {
+        assert_eq!(app_data.positions.len(), app_data.elements.len());
+        app_data.elements.iter().for_each(|element| {
+            assert_eq!(app_data.get_element(&element.id), Some(element));
+        });
+    }
+
+    #[test]
+    fn lookups_follow_deletes_and_undo() {
+        let mut app_data = AppData::default();
+        let ids: Vec<ElementId> = (0..5)
+            .map(|i| {
+                let x = i * 100;
+                add_shape(
+                    &mut app_data,
+                    WidgetKind::Rectangle,
+                    Rect::new(x, 0, x + 50, 50),
+                )
+            })
+            .collect();
+        let mut label = Element::new(WidgetKind::Text, ElementConfig::default());
+        label.container_id = Some(ids[0].clone());
+        label.rect = Rect::new(10, 10, 40, 40);
+        let label_id = label.id.clone();
+        app_data.add_element(label);
+        assert_positions(&app_data);
+
+        app_data.execute(Command::DeleteElements(vec![
+            ids[1].clone(),
+            ids[3].clone(),
+        ]));
+        assert_positions(&app_data);
+        assert!(app_data.get_element(&ids[1]).is_none());
+
+        app_data.undo();
+        assert_positions(&app_data);
+        let in_rect: Vec<&ElementId> = app_data
+            .get_elements_in_rect(Rect::new(0, 0, 1000, 100))
+            .into_iter()
+            .map(|element| &element.id)
+            .collect();
+        let mut expected: Vec<&ElementId> = ids.iter().collect();
+        expected.push(&label_id);
+        assert_eq!(in_rect, expected);
+        assert_eq!(
+            app_data.get_bound_text(&ids[0]).map(|label| &label.id),
+            Some(&label_id)
+        );
+
+        app_data.execute(Command::DeleteElements(vec![label_id.clone()]));
+        assert!(app_data.get_bound_text(&ids[0]).is_none());
+        assert!(app_data.bound_elements.is_empty());
+    }
+
+    #[test]
+    fn moving_a_container_moves_its_label() {
+        let mut app_data = AppData::default();
+        let id = add_shape(
+            &mut app_data,
+            WidgetKind::Rectangle,
+            Rect::new(0, 0, 100, 100),
+        );
+        let mut label = Element::new(WidgetKind::Text, ElementConfig::default());
+        label.container_id = Some(id.clone());
+        label.rect = Rect::new(10, 40, 90, 60);
+        let label_id = label.id.clone();
+        app_data.add_element(label);
+
+        app_data.select_element(&id, false);
+        app_data.move_selected_elements(5, 7);
+        assert_eq!(
+            app_data.get_element(&id).unwrap().rect,
+            Rect::new(5, 7, 105, 107)
+        );
+        assert_eq!(
+            app_data.get_element(&label_id).unwrap().rect,
+            Rect::new(15, 47, 95, 67)
+        );
+    }
+
+    /// Two rectangles side by side with an arrow bound between them.
+    fn add_bound_arrow(app_data: &mut AppData) -> (ElementId, ElementId, ElementId) {
+        let start = add_shape(app_data, WidgetKind::Rectangle, Rect::new(0, 0, 100, 100));
+        let end = add_shape(app_data, WidgetKind::Rectangle, Rect::new(200, 0, 300, 100));
+        let arrow = add_shape(app_data, WidgetKind::Arrow, Rect::new(50, 50, 250, 50));
+        app_data.bind_arrow(&arrow, 1.0);
+        (start, end, arrow)
+    }
+
+    fn get_endpoints(app_data: &AppData, id: &ElementId) -> ((f64, f64), (f64, f64)) {
+        get_arrow_endpoints(app_data.get_element(id).unwrap())
+    }
+
+    fn assert_bound_elements(app_data: &AppData) {
+        let mut expected: HashMap<ElementId, HashSet<ElementId>> = HashMap::new();
+        app_data.elements.iter().for_each(|element| {
+            get_bound_targets(element).into_iter().for_each(|target| {
+                expected
+                    .entry(target)
+                    .or_default()
+                    .insert(element.id.clone());
+            });
+        });
+        assert_eq!(app_data.bound_elements, expected);
+    }
+
+    #[test]
+    fn arrow_binds_and_snaps_to_outlines() {
+        let mut app_data = AppData::default();
+        let (start, end, arrow) = add_bound_arrow(&mut app_data);
+        let element = app_data.get_element(&arrow).unwrap();
+        assert_eq!(element.start_binding, Some(start));
+        assert_eq!(element.end_binding, Some(end));
+        assert_eq!(
+            get_endpoints(&app_data, &arrow),
+            ((104.0, 50.0), (196.0, 50.0))
+        );
+        assert_bound_elements(&app_data);
+    }
+
+    #[test]
+    fn bound_arrow_follows_move_and_resize() {
+        let mut app_data = AppData::default();
+        let (_, end, arrow) = add_bound_arrow(&mut app_data);
+
+        app_data.select_element(&end, false);
+        app_data.move_selected_elements(0, 100);
+        assert_eq!(
+            get_endpoints(&app_data, &arrow),
+            ((104.0, 77.0), (196.0, 123.0))
+        );
+
+        app_data.resize_element(&end, Rect::new(300, 0, 400, 100));
+        assert_eq!(
+            get_endpoints(&app_data, &arrow),
+            ((104.0, 50.0), (296.0, 50.0))
+        );
+        assert_bound_elements(&app_data);
+    }
+
+    #[test]
+    fn dragging_a_bound_element_undoes_in_one_step() {
+        let mut app_data = AppData::default();
+        let (_, end, _) = add_bound_arrow(&mut app_data);
+        app_data.select_element(&end, false);
+        let before = app_data.elements.clone();
+
+        app_data.begin_change();
+        for _ in 0..30 {
+            app_data.move_selected_elements(1, 3);
+        }
+        app_data.end_change();
+        app_data.undo();
+        assert_eq!(app_data.elements, before);
+        assert_bound_elements(&app_data);
+    }
+
+    #[test]
+    fn moving_an_arrow_alone_unbinds_it() {
+        let mut app_data = AppData::default();
+        let (_, _, arrow) = add_bound_arrow(&mut app_data);
+
+        app_data.select_element(&arrow, false);
+        app_data.move_selected_elements(0, 300);
+        let element = app_data.get_element(&arrow).unwrap();
+        assert_eq!(element.start_binding, None);
+        assert_eq!(element.end_binding, None);
+        assert!(app_data.bound_elements.is_empty());
+    }
+
+    #[test]
+    fn deleting_a_target_unbinds_arrows_in_one_undo_entry() {
+        let mut app_data = AppData::default();
+        let (start, end, arrow) = add_bound_arrow(&mut app_data);
+        app_data.select_element(&end, false);
+        let before = app_data.elements.clone();
+
+        app_data.begin_change();
+        app_data.delete_selected_elements();
+        app_data.end_change();
+        let element = app_data.get_element(&arrow).unwrap();
+        assert_eq!(element.start_binding, Some(start));
+        assert_eq!(element.end_binding, None);
+        assert_bound_elements(&app_data);
+
+        app_data.undo();
+        assert_eq!(app_data.elements, before);
+        assert_bound_elements(&app_data);
+    }
+}
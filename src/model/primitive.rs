@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+pub use crate::rough::core::Op;
+use crate::rough::core::{Drawable, OpSetKind};
+
+/// A drawing primitive of an element. Path coordinates are relative to the
+/// start of `Element::rect`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Primitive {
+    Stroke {
+        ops: Vec<Op>,
+        color: String,
+        width: f64,
+    },
+    Fill {
+        ops: Vec<Op>,
+        color: String,
+    },
+    Text {
+        text: String,
+    },
+}
+
+impl Primitive {
+    pub fn from_drawable(drawable: Drawable) -> Vec<Primitive> {
+        let options = drawable.options;
+        let fill = options.fill.clone().unwrap_or_default();
+        drawable
+            .sets
+            .into_iter()
+            .map(|set| match set.kind {
+                OpSetKind::Path => Primitive::Stroke {
+                    ops: set.ops,
+                    color: options.stroke.clone(),
+                    width: options.stroke_width,
+                },
+                OpSetKind::FillPath => Primitive::Fill {
+                    ops: set.ops,
+                    color: fill.clone(),
+                },
+                OpSetKind::FillSketch => Primitive::Stroke {
+                    ops: set.ops,
+                    color: fill.clone(),
+                    width: options.get_fill_weight(),
+                },
+            })
+            .collect()
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct Rect {
     pub start_x: i32,
     pub start_y: i32,
@@ -8,36 +8,27 @@ pub struct Rect {
     pub end_y: i32,
 }
 
-impl Default for Rect {
-    fn default() -> Self {
-        Rect {
-            start_x: 0,
-            start_y: 0,
-            end_x: 0,
-            end_y: 0,
-        }
-    }
-}
-
 impl Rect {
     pub fn new(start_x: i32, start_y: i32, end_x: i32, end_y: i32) -> Rect {
         Rect {
-            start_x: start_x,
-            start_y: start_y,
-            end_x: end_x,
-            end_y: end_y,
+            start_x,
+            start_y,
+            end_x,
+            end_y,
         }
     }
 
     pub fn is_inside(&self, rect: Rect) -> bool {
         let rect = fix_rect(&rect);
-        let target_rect = fix_rect(&self);
-        if target_rect.start_x > rect.start_x && target_rect.start_y > rect.start_y {
-            if target_rect.end_x < rect.end_x && target_rect.end_y < rect.end_y {
-                return true;
-            }
+        let target_rect = fix_rect(self);
+        if target_rect.start_x > rect.start_x
+            && target_rect.start_y > rect.start_y
+            && target_rect.end_x < rect.end_x
+            && target_rect.end_y < rect.end_y
+        {
+            return true;
         }
-        return false;
+        false
     }
 
     pub fn get_width(&self) -> i32 {
@@ -48,17 +39,52 @@ impl Rect {
         self.end_y - self.start_y
     }
 
+    pub fn get_center(&self) -> (f64, f64) {
+        (
+            (self.start_x + self.end_x) as f64 / 2.0,
+            (self.start_y + self.end_y) as f64 / 2.0,
+        )
+    }
+
+    /**
+     * Rectangle with start at the top left corner
+     */
+    pub fn normalize(&self) -> Rect {
+        fix_rect(self)
+    }
+
+    /**
+     * Smallest rectangle containing both rectangles
+     */
+    pub fn union(&self, rect: Rect) -> Rect {
+        let a = fix_rect(self);
+        let b = fix_rect(&rect);
+        Rect {
+            start_x: a.start_x.min(b.start_x),
+            start_y: a.start_y.min(b.start_y),
+            end_x: a.end_x.max(b.end_x),
+            end_y: a.end_y.max(b.end_y),
+        }
+    }
+
+    /**
+     * Check if two rectangles overlap, touching edges count
+     */
+    pub fn intersects(&self, rect: Rect) -> bool {
+        let a = fix_rect(self);
+        let b = fix_rect(&rect);
+        a.start_x <= b.end_x && b.start_x <= a.end_x && a.start_y <= b.end_y && b.start_y <= a.end_y
+    }
+
     /**
      * Check if a point is inside the rectangle
      */
     pub fn is_in_point(&self, x: i32, y: i32) -> bool {
-        let rect = fix_rect(&self);
-        if x > rect.start_x && x < rect.end_x {
-            if y > rect.start_y && y < rect.end_y {
-                return true;
-            }
+        let rect = fix_rect(self);
+        if x > rect.start_x && x < rect.end_x && y > rect.start_y && y < rect.end_y {
+            return true;
         }
-        return false;
+        false
     }
 }
 
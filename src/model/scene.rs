@@ -0,0 +1,487 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use super::{
+    binding::BINDING_GAP,
+    element::{Element, ElementConfig},
+    font::{FontFamily, TextAlign},
+    id::ElementId,
+    primitive::Primitive,
+    rect::Rect,
+    widget_kind::WidgetKind,
+};
+
+const SCENE_TYPE: &str = "excalidraw";
+const SCENE_VERSION: u32 = 2;
+const SCENE_SOURCE: &str = "https://github.com/AlanLang/excalidraw-wasm";
+/// Key of an imported arrow's points in `Element::scene_properties`.
+const POINTS: &str = "points";
+
+/// The `.excalidraw` file format used by upstream Excalidraw.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scene {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub source: String,
+    #[serde(default)]
+    pub elements: Vec<SceneElement>,
+    #[serde(default)]
+    pub app_state: SceneAppState,
+    #[serde(default)]
+    pub files: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SceneAppState {
+    #[serde(default)]
+    pub view_background_color: Option<String>,
+    #[serde(default)]
+    pub grid_size: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SceneElement {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    #[serde(default)]
+    pub angle: f64,
+    #[serde(default = "default_stroke_color")]
+    pub stroke_color: String,
+    #[serde(default = "default_background_color")]
+    pub background_color: String,
+    #[serde(default)]
+    pub seed: u32,
+    #[serde(default = "default_version")]
+    pub version: u32,
+    #[serde(default)]
+    pub version_nonce: u32,
+    #[serde(default)]
+    pub is_deleted: bool,
+    #[serde(default)]
+    pub bound_elements: Option<Vec<Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub points: Option<Vec<[f64; 2]>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font_size: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font_family: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_align: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vertical_align: Option<String>,
+    /// The element a text is the label of.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_binding: Option<SceneBinding>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_binding: Option<SceneBinding>,
+    /// Everything this app doesn't use, such as the fill style, stroke width
+    /// and groups. Kept on the element so saving writes it back unchanged.
+    #[serde(flatten)]
+    pub properties: Map<String, Value>,
+}
+
+/// The element one end of an arrow is attached to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SceneBinding {
+    pub element_id: String,
+    /// Where the arrow points at relative to the element's center, upstream
+    /// Excalidraw's own layout. Bound arrows here always aim at the center.
+    #[serde(default)]
+    pub focus: f64,
+    #[serde(default)]
+    pub gap: f64,
+}
+
+impl SceneBinding {
+    fn new(element_id: &ElementId) -> Self {
+        SceneBinding {
+            element_id: element_id.to_string(),
+            focus: 0.0,
+            gap: BINDING_GAP,
+        }
+    }
+}
+
+impl Scene {
+    pub fn new(elements: &[Element], view_bg_color: &str) -> Scene {
+        let mut scene_elements: Vec<SceneElement> = elements
+            .iter()
+            .filter_map(SceneElement::from_element)
+            .collect();
+        link_bound_elements(&mut scene_elements);
+        Scene {
+            kind: SCENE_TYPE.into(),
+            version: SCENE_VERSION,
+            source: SCENE_SOURCE.into(),
+            elements: scene_elements,
+            app_state: SceneAppState {
+                view_background_color: Some(view_bg_color.into()),
+                grid_size: None,
+            },
+            files: Default::default(),
+        }
+    }
+
+    pub fn parse(data: &str) -> Result<Scene, String> {
+        let scene: Scene = serde_json::from_str(data).map_err(|err| err.to_string())?;
+        if scene.kind != SCENE_TYPE {
+            return Err(format!("unsupported scene type: {}", scene.kind));
+        }
+        Ok(scene)
+    }
+
+    /// Elements of a type this app can't draw are skipped.
+    pub fn to_elements(&self) -> Vec<Element> {
+        self.elements
+            .iter()
+            .filter(|element| !element.is_deleted)
+            .filter_map(SceneElement::to_element)
+            .collect()
+    }
+}
+
+impl SceneElement {
+    pub fn from_element(element: &Element) -> Option<SceneElement> {
+        let rect = element.rect;
+        let (x1, y1, x2, y2) = (
+            rect.start_x.min(rect.end_x) as f64,
+            rect.start_y.min(rect.end_y) as f64,
+            rect.start_x.max(rect.end_x) as f64,
+            rect.start_y.max(rect.end_y) as f64,
+        );
+        let mut properties = element.scene_properties.clone();
+        let stored_points = properties.remove(POINTS);
+        get_default_properties(element.kind)
+            .into_iter()
+            .for_each(|(key, value)| {
+                properties.entry(key).or_insert(value);
+            });
+        let mut scene_element = SceneElement {
+            id: element.id.to_string(),
+            kind: String::new(),
+            x: x1,
+            y: y1,
+            width: x2 - x1,
+            height: y2 - y1,
+            angle: element.angle,
+            stroke_color: element.config.item_stroke_color.clone(),
+            background_color: element.config.item_bg_color.clone(),
+            seed: element.seed,
+            version: element.version.max(1),
+            version_nonce: element.version_nonce,
+            is_deleted: false,
+            bound_elements: None,
+            points: None,
+            text: None,
+            original_text: None,
+            font_size: None,
+            font_family: None,
+            text_align: None,
+            vertical_align: None,
+            container_id: element.container_id.as_ref().map(|id| id.to_string()),
+            start_binding: element.start_binding.as_ref().map(SceneBinding::new),
+            end_binding: element.end_binding.as_ref().map(SceneBinding::new),
+            properties,
+        };
+        match element.kind {
+            WidgetKind::Rectangle => scene_element.kind = "rectangle".into(),
+            WidgetKind::Ellipse => scene_element.kind = "ellipse".into(),
+            WidgetKind::Arrow => {
+                // arrows keep their direction: x/y is the tail, points are relative to it
+                scene_element.kind = "arrow".into();
+                let end = (rect.get_width().into(), rect.get_height().into());
+                let points = stored_points
+                    .and_then(|points| serde_json::from_value::<Vec<[f64; 2]>>(points).ok())
+                    .filter(|points| fits_arrow(points, end))
+                    .unwrap_or_else(|| vec![[0.0, 0.0], [end.0, end.1]]);
+                let [first_x, first_y] = points[0];
+                let (min_x, min_y, max_x, max_y) = points.iter().fold(
+                    (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+                    |(min_x, min_y, max_x, max_y), &[x, y]| {
+                        (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+                    },
+                );
+                scene_element.x = rect.start_x as f64 - first_x;
+                scene_element.y = rect.start_y as f64 - first_y;
+                scene_element.width = max_x - min_x;
+                scene_element.height = max_y - min_y;
+                scene_element.points = Some(points);
+            }
+            WidgetKind::Text => {
+                let text = element.get_text()?.to_string();
+                scene_element.kind = "text".into();
+                scene_element.text = Some(text.clone());
+                scene_element.original_text = Some(text);
+                scene_element.font_size = Some(element.config.font_size);
+                scene_element.font_family = Some(element.config.font_family.to_scene());
+                scene_element.text_align = Some(element.config.text_align.as_str().into());
+                let vertical_align = match element.container_id {
+                    Some(_) => "middle",
+                    None => "top",
+                };
+                scene_element.vertical_align = Some(vertical_align.into());
+            }
+            WidgetKind::Selection => return None,
+        }
+        Some(scene_element)
+    }
+
+    pub fn to_element(&self) -> Option<Element> {
+        let kind = match self.kind.as_str() {
+            "rectangle" => WidgetKind::Rectangle,
+            "ellipse" => WidgetKind::Ellipse,
+            "arrow" => WidgetKind::Arrow,
+            "text" => WidgetKind::Text,
+            _ => return None,
+        };
+        let rect = match (&self.points, kind) {
+            (Some(points), WidgetKind::Arrow) if points.len() >= 2 => {
+                let [start_x, start_y] = points[0];
+                let [end_x, end_y] = points[points.len() - 1];
+                Rect::new(
+                    (self.x + start_x).round() as i32,
+                    (self.y + start_y).round() as i32,
+                    (self.x + end_x).round() as i32,
+                    (self.y + end_y).round() as i32,
+                )
+            }
+            _ => Rect::new(
+                self.x.round() as i32,
+                self.y.round() as i32,
+                (self.x + self.width).round() as i32,
+                (self.y + self.height).round() as i32,
+            ),
+        };
+
+        let mut config =
+            ElementConfig::new(self.stroke_color.clone(), self.background_color.clone());
+        if let Some(font_size) = self.font_size {
+            config.font_size = font_size;
+        }
+        if let Some(font_family) = self.font_family {
+            config.font_family = FontFamily::from_scene(font_family);
+        }
+        if let Some(text_align) = &self.text_align {
+            config.text_align = TextAlign::from_name(text_align);
+        }
+        let mut element = Element::new(kind, config);
+        element.id = ElementId::from(self.id.as_str());
+        element.scene_properties = self.properties.clone();
+        // 箭头只绘制首尾两点，中间的点原样保存
+        if let (Some(points), WidgetKind::Arrow) = (&self.points, kind) {
+            element
+                .scene_properties
+                .insert(POINTS.into(), json!(points));
+        }
+        element.seed = self.seed;
+        element.rect = rect;
+        element.angle = self.angle;
+        element.container_id = self.container_id.as_deref().map(ElementId::from);
+        if kind == WidgetKind::Arrow {
+            let to_id = |binding: &SceneBinding| ElementId::from(binding.element_id.as_str());
+            element.start_binding = self.start_binding.as_ref().map(to_id);
+            element.end_binding = self.end_binding.as_ref().map(to_id);
+        }
+        if kind == WidgetKind::Text {
+            let text = self.text.clone().unwrap_or_default();
+            element.update_shapes(vec![Primitive::Text { text }]);
+        } else {
+            element.regenerate_shapes();
+        }
+        element.version = self.version;
+        element.version_nonce = self.version_nonce;
+        Some(element)
+    }
+}
+
+/**
+ * 元素通过 `boundElements` 记录它的标签和绑定到它的箭头，Excalidraw 需要两边都有记录
+ */
+fn link_bound_elements(elements: &mut [SceneElement]) {
+    let bindings: Vec<(String, serde_json::Value)> = elements
+        .iter()
+        .flat_map(|element| {
+            let bound = serde_json::json!({ "type": element.kind, "id": element.id });
+            let arrow_ends = [&element.start_binding, &element.end_binding]
+                .into_iter()
+                .flatten()
+                .map(|binding| binding.element_id.clone());
+            element
+                .container_id
+                .clone()
+                .into_iter()
+                .chain(arrow_ends)
+                .map(move |target_id| (target_id, bound.clone()))
+        })
+        .collect();
+    bindings.into_iter().for_each(|(target_id, bound)| {
+        if let Some(target) = elements.iter_mut().find(|element| element.id == target_id) {
+            target
+                .bound_elements
+                .get_or_insert_with(Vec::new)
+                .push(bound);
+        }
+    });
+}
+
+/**
+ * 保存的箭头中间的点只在首尾两点的位置与箭头一致时使用，
+ * 箭头在这里被改变后按直线保存
+ */
+fn fits_arrow(points: &[[f64; 2]], (end_x, end_y): (f64, f64)) -> bool {
+    match (points.first(), points.last()) {
+        (Some([first_x, first_y]), Some([last_x, last_y])) if points.len() >= 2 => {
+            // 导入时首尾两点分别取整，相对位置最多相差 1
+            (last_x - first_x - end_x).abs() <= 1.0 && (last_y - first_y - end_y).abs() <= 1.0
+        }
+        _ => false,
+    }
+}
+
+/// Upstream properties this app doesn't use, as saved for elements created here.
+fn get_default_properties(kind: WidgetKind) -> Map<String, Value> {
+    let mut properties = json!({
+        "fillStyle": "hachure",
+        "strokeWidth": 1,
+        "strokeStyle": "solid",
+        "roughness": 1,
+        "opacity": 100,
+        "groupIds": [],
+        "locked": false,
+    });
+    if kind == WidgetKind::Arrow {
+        properties["startArrowhead"] = Value::Null;
+        properties["endArrowhead"] = json!("arrow");
+    }
+    match properties {
+        Value::Object(properties) => properties,
+        _ => unreachable!(),
+    }
+}
+
+fn default_stroke_color() -> String {
+    "#000000".into()
+}
+
+fn default_background_color() -> String {
+    "transparent".into()
+}
+
+fn default_version() -> u32 {
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UPSTREAM_SCENE: &str = r##"{
+        "type": "excalidraw",
+        "version": 2,
+        "source": "https://excalidraw.com",
+        "elements": [
+            {
+                "id": "box", "type": "rectangle", "x": 10, "y": 20, "width": 200, "height": 100,
+                "angle": 0, "strokeColor": "#1e1e1e", "backgroundColor": "#ffec99",
+                "fillStyle": "cross-hatch", "strokeWidth": 4, "strokeStyle": "dashed",
+                "roughness": 2, "opacity": 60, "groupIds": ["group"], "frameId": null,
+                "roundness": {"type": 3}, "seed": 1234, "version": 7, "versionNonce": 99,
+                "isDeleted": false, "boundElements": null, "updated": 1700000000000,
+                "link": "https://example.com", "locked": true
+            },
+            {
+                "id": "line", "type": "arrow", "x": 300, "y": 40, "width": 120, "height": 80,
+                "angle": 0, "strokeColor": "#e03131", "backgroundColor": "transparent",
+                "fillStyle": "solid", "strokeWidth": 2, "strokeStyle": "solid",
+                "roughness": 0, "opacity": 100, "groupIds": ["group"], "seed": 5,
+                "version": 3, "versionNonce": 8, "isDeleted": false, "boundElements": null,
+                "locked": false, "points": [[0, 0], [60, 80], [120, 0]],
+                "lastCommittedPoint": null, "startArrowhead": "dot", "endArrowhead": null
+            },
+            {
+                "id": "note", "type": "text", "x": 10, "y": 200, "width": 50, "height": 25,
+                "angle": 0, "strokeColor": "#000000", "backgroundColor": "transparent",
+                "fillStyle": "hachure", "strokeWidth": 1, "strokeStyle": "solid",
+                "roughness": 1, "opacity": 100, "groupIds": [], "seed": 6, "version": 2,
+                "versionNonce": 4, "isDeleted": false, "boundElements": null, "locked": false,
+                "text": "hello", "originalText": "hello", "fontSize": 28, "fontFamily": 3,
+                "textAlign": "right", "verticalAlign": "top", "baseline": 18, "lineHeight": 1.25
+            }
+        ],
+        "appState": {"viewBackgroundColor": "#f8f9fa", "gridSize": null},
+        "files": {}
+    }"##;
+
+    #[test]
+    fn round_trip_keeps_every_property() {
+        let scene = Scene::parse(UPSTREAM_SCENE).unwrap();
+        let elements = scene.to_elements();
+        let saved = Scene::new(&elements, "#f8f9fa");
+
+        let original: Value = serde_json::from_str(UPSTREAM_SCENE).unwrap();
+        let saved = serde_json::to_value(&saved).unwrap();
+        let original = original["elements"].as_array().unwrap();
+        let saved = saved["elements"].as_array().unwrap();
+        assert_eq!(saved.len(), original.len());
+        for (original, saved) in original.iter().zip(saved) {
+            for (key, value) in original.as_object().unwrap() {
+                let saved_value = saved.get(key).unwrap_or(&Value::Null);
+                assert!(
+                    is_same(value, saved_value),
+                    "{}.{key}: {value} became {saved_value}",
+                    original["id"]
+                );
+            }
+        }
+    }
+
+    /// Equal values, integers and floats compare by value.
+    fn is_same(a: &Value, b: &Value) -> bool {
+        match (a, b) {
+            (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+            (Value::Array(a), Value::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| is_same(a, b))
+            }
+            _ => a == b,
+        }
+    }
+
+    #[test]
+    fn edited_arrow_is_saved_straight() {
+        let scene = Scene::parse(UPSTREAM_SCENE).unwrap();
+        let mut arrow = scene.to_elements().remove(1);
+        arrow.rect = Rect::new(0, 0, 50, 50);
+        let saved = SceneElement::from_element(&arrow).unwrap();
+        assert_eq!(saved.points, Some(vec![[0.0, 0.0], [50.0, 50.0]]));
+        assert!(!saved.properties.contains_key(POINTS));
+    }
+
+    #[test]
+    fn new_elements_get_upstream_defaults() {
+        let mut element = Element::new(WidgetKind::Arrow, ElementConfig::default());
+        element.rect = Rect::new(0, 0, 10, 10);
+        let saved = serde_json::to_value(SceneElement::from_element(&element).unwrap()).unwrap();
+        assert_eq!(saved["strokeWidth"], 1);
+        assert_eq!(saved["fillStyle"], "hachure");
+        assert_eq!(saved["endArrowhead"], "arrow");
+        assert_eq!(saved["groupIds"], json!([]));
+    }
+}
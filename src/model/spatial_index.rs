@@ -0,0 +1,202 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{id::ElementId, rect::Rect};
+
+/// Side of a grid cell in scene units.
+const CELL_SIZE: i32 = 256;
+/// Elements spanning more cells than this are kept in a separate list that
+/// every query checks, so one huge element doesn't fill thousands of cells.
+const MAX_CELLS: i64 = 64;
+
+/// A uniform grid over the bounds of elements, so point and rectangle
+/// queries only look at elements near the query.
+#[derive(Debug, Default, Clone)]
+pub struct SpatialIndex {
+    cells: HashMap<(i32, i32), HashSet<ElementId>>,
+    oversized: HashSet<ElementId>,
+    bounds: HashMap<ElementId, Rect>,
+}
+
+impl SpatialIndex {
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.oversized.clear();
+        self.bounds.clear();
+    }
+
+    /**
+     * 插入元素，已存在时更新它的区域
+     */
+    pub fn insert(&mut self, id: ElementId, bounds: Rect) {
+        self.remove(&id);
+        let bounds = bounds.normalize();
+        let (start, end) = get_cell_range(bounds);
+        let count = (end.0 - start.0 + 1) as i64 * (end.1 - start.1 + 1) as i64;
+        if count > MAX_CELLS {
+            self.oversized.insert(id.clone());
+        } else {
+            for cell_x in start.0..=end.0 {
+                for cell_y in start.1..=end.1 {
+                    self.cells
+                        .entry((cell_x, cell_y))
+                        .or_default()
+                        .insert(id.clone());
+                }
+            }
+        }
+        self.bounds.insert(id, bounds);
+    }
+
+    pub fn remove(&mut self, id: &ElementId) {
+        let bounds = match self.bounds.remove(id) {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        if self.oversized.remove(id) {
+            return;
+        }
+        let (start, end) = get_cell_range(bounds);
+        for cell_x in start.0..=end.0 {
+            for cell_y in start.1..=end.1 {
+                if let Some(ids) = self.cells.get_mut(&(cell_x, cell_y)) {
+                    ids.remove(id);
+                    if ids.is_empty() {
+                        self.cells.remove(&(cell_x, cell_y));
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn get_bounds(&self, id: &ElementId) -> Option<Rect> {
+        self.bounds.get(id).copied()
+    }
+
+    /// Ids of the elements whose bounds overlap `rect`.
+    pub fn query(&self, rect: Rect) -> HashSet<ElementId> {
+        let rect = rect.normalize();
+        let (start, end) = get_cell_range(rect);
+        let count = (end.0 - start.0 + 1) as i64 * (end.1 - start.1 + 1) as i64;
+        // 查询区域比已有的格子还多时，直接检查每个元素更快
+        let candidates: Box<dyn Iterator<Item = &ElementId>> = if count > self.cells.len() as i64 {
+            Box::new(self.bounds.keys())
+        } else {
+            Box::new(
+                (start.0..=end.0)
+                    .flat_map(|cell_x| (start.1..=end.1).map(move |cell_y| (cell_x, cell_y)))
+                    .filter_map(|cell| self.cells.get(&cell))
+                    .flatten()
+                    .chain(self.oversized.iter()),
+            )
+        };
+        candidates
+            .filter(|id| {
+                self.bounds
+                    .get(*id)
+                    .map(|bounds| bounds.intersects(rect))
+                    .unwrap_or(false)
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+fn get_cell_range(rect: Rect) -> ((i32, i32), (i32, i32)) {
+    (
+        (
+            rect.start_x.div_euclid(CELL_SIZE),
+            rect.start_y.div_euclid(CELL_SIZE),
+        ),
+        (
+            rect.end_x.div_euclid(CELL_SIZE),
+            rect.end_y.div_euclid(CELL_SIZE),
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&ElementId]) -> HashSet<ElementId> {
+        ids.iter().map(|id| (*id).clone()).collect()
+    }
+
+    #[test]
+    fn insert_move_and_remove() {
+        let mut index = SpatialIndex::default();
+        let (a, b) = (ElementId::new(), ElementId::new());
+        index.insert(a.clone(), Rect::new(0, 0, 10, 10));
+        index.insert(b.clone(), Rect::new(500, 500, 510, 510));
+        assert_eq!(index.query(Rect::new(0, 0, 20, 20)), ids(&[&a]));
+        assert_eq!(index.query(Rect::new(20, 20, 30, 30)), ids(&[]));
+
+        // 再次插入时移到新的区域，旧的格子不再包含它
+        index.insert(a.clone(), Rect::new(600, 600, 610, 610));
+        assert_eq!(index.query(Rect::new(0, 0, 20, 20)), ids(&[]));
+        assert_eq!(index.query(Rect::new(505, 505, 605, 605)), ids(&[&a, &b]));
+        assert_eq!(index.get_bounds(&a), Some(Rect::new(600, 600, 610, 610)));
+
+        index.remove(&b);
+        assert_eq!(index.query(Rect::new(505, 505, 605, 605)), ids(&[&a]));
+        assert_eq!(index.get_bounds(&b), None);
+        index.remove(&a);
+        assert!(index.cells.is_empty());
+        assert!(index.bounds.is_empty());
+    }
+
+    #[test]
+    fn oversized_elements_skip_the_grid() {
+        let mut index = SpatialIndex::default();
+        let (huge, small) = (ElementId::new(), ElementId::new());
+        index.insert(huge.clone(), Rect::new(0, 0, 5000, 5000));
+        index.insert(small.clone(), Rect::new(0, 0, 10, 10));
+        assert_eq!(index.oversized, ids(&[&huge]));
+        assert_eq!(index.cells.len(), 1);
+
+        assert_eq!(
+            index.query(Rect::new(4000, 4000, 4001, 4001)),
+            ids(&[&huge])
+        );
+        assert_eq!(index.query(Rect::new(5, 5, 6, 6)), ids(&[&huge, &small]));
+        assert_eq!(index.query(Rect::new(6000, 0, 6001, 1)), ids(&[]));
+
+        index.remove(&huge);
+        assert!(index.oversized.is_empty());
+        assert_eq!(index.query(Rect::new(5, 5, 6, 6)), ids(&[&small]));
+    }
+
+    #[test]
+    fn negative_coordinates_use_the_cells_below_zero() {
+        let mut index = SpatialIndex::default();
+        let (left, middle) = (ElementId::new(), ElementId::new());
+        index.insert(left.clone(), Rect::new(-10, -10, -5, -5));
+        index.insert(middle.clone(), Rect::new(-5, -5, 5, 5));
+        let cells: HashSet<(i32, i32)> = index.cells.keys().copied().collect();
+        assert_eq!(cells, HashSet::from([(-1, -1), (-1, 0), (0, -1), (0, 0)]));
+
+        assert_eq!(index.query(Rect::new(-20, -20, -8, -8)), ids(&[&left]));
+        assert_eq!(index.query(Rect::new(-300, 0, -200, 10)), ids(&[]));
+        assert_eq!(index.query(Rect::new(1, 1, 2, 2)), ids(&[&middle]));
+    }
+
+    #[test]
+    fn large_query_checks_every_element() {
+        let mut index = SpatialIndex::default();
+        let (a, b) = (ElementId::new(), ElementId::new());
+        index.insert(a.clone(), Rect::new(0, 0, 10, 10));
+        index.insert(b.clone(), Rect::new(-3000, 2000, -2990, 2010));
+        // 查询覆盖的格子远多于已有的两个格子
+        let all = Rect::new(-10_000, -10_000, 10_000, 10_000);
+        assert_eq!(index.query(all), ids(&[&a, &b]));
+        assert_eq!(
+            index.query(Rect::new(-10_000, -10_000, 10_000, 100)),
+            ids(&[&a])
+        );
+        // 反向的区域先取正
+        assert_eq!(
+            index.query(Rect::new(10_000, 10_000, -10_000, 100)),
+            ids(&[&b])
+        );
+    }
+}
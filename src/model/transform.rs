@@ -0,0 +1,17 @@
+use super::rect::Rect;
+
+/// A change of where an element is placed in the scene. All values are in
+/// world coordinates; `Element::transform` keeps the local geometry in
+/// `Element::shapes` in sync with it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Transform {
+    Translate(i32, i32),
+    /// Gives the element a new `rect`, keeping its orientation.
+    Resize(Rect),
+    /// Sets the clockwise angle around the element's center, in radians.
+    Rotate(f64),
+    /// Mirrors the element across the vertical line `x = axis`.
+    FlipHorizontal(f64),
+    /// Mirrors the element across the horizontal line `y = axis`.
+    FlipVertical(f64),
+}
@@ -0,0 +1,174 @@
+use super::rect::Rect;
+
+pub const MIN_ZOOM: f64 = 0.1;
+pub const MAX_ZOOM: f64 = 30.0;
+
+/// The camera over the infinite canvas. A scene point is drawn at
+/// `(point + scroll) * zoom` in css pixels, which the canvas backing store
+/// scales by `pixel_ratio`. Mouse events are in css pixels.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Viewport {
+    pub scroll_x: f64,
+    pub scroll_y: f64,
+    pub zoom: f64,
+    /// Device pixels per css pixel, `window.devicePixelRatio`.
+    pub pixel_ratio: f64,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Viewport {
+            scroll_x: 0.0,
+            scroll_y: 0.0,
+            zoom: 1.0,
+            pixel_ratio: 1.0,
+        }
+    }
+}
+
+impl Viewport {
+    /**
+     * 将画布上的坐标（如鼠标的 offset）转换为场景坐标
+     */
+    pub fn to_scene(&self, x: i32, y: i32) -> (i32, i32) {
+        let (x, y) = self.to_scene_f64(x.into(), y.into());
+        (x.round() as i32, y.round() as i32)
+    }
+
+    pub fn to_scene_f64(&self, x: f64, y: f64) -> (f64, f64) {
+        (x / self.zoom - self.scroll_x, y / self.zoom - self.scroll_y)
+    }
+
+    pub fn to_screen_f64(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            (x + self.scroll_x) * self.zoom,
+            (y + self.scroll_y) * self.zoom,
+        )
+    }
+
+    /**
+     * 平移画布，偏移量为屏幕像素
+     */
+    pub fn pan(&mut self, offset_x: f64, offset_y: f64) {
+        self.scroll_x += offset_x / self.zoom;
+        self.scroll_y += offset_y / self.zoom;
+    }
+
+    /**
+     * 以屏幕上的 (x, y) 为中心缩放，缩放前后该点下的场景坐标不变
+     */
+    pub fn zoom_at(&mut self, x: f64, y: f64, zoom: f64) {
+        let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        let (scene_x, scene_y) = self.to_scene_f64(x, y);
+        self.zoom = zoom;
+        self.scroll_x = x / zoom - scene_x;
+        self.scroll_y = y / zoom - scene_y;
+    }
+
+    /// The part of the scene visible on a canvas of the given size.
+    pub fn get_visible_rect(&self, width: f64, height: f64) -> Rect {
+        self.to_scene_rect(Rect::new(0, 0, width.ceil() as i32, height.ceil() as i32))
+    }
+
+    /// The smallest scene rectangle covering a rectangle on the canvas.
+    pub fn to_scene_rect(&self, rect: Rect) -> Rect {
+        let rect = rect.normalize();
+        let (start_x, start_y) = self.to_scene_f64(rect.start_x.into(), rect.start_y.into());
+        let (end_x, end_y) = self.to_scene_f64(rect.end_x.into(), rect.end_y.into());
+        outer_rect(start_x, start_y, end_x, end_y)
+    }
+
+    /// The smallest canvas rectangle covering a rectangle of the scene.
+    pub fn to_screen_rect(&self, rect: Rect) -> Rect {
+        let rect = rect.normalize();
+        let (start_x, start_y) = self.to_screen_f64(rect.start_x.into(), rect.start_y.into());
+        let (end_x, end_y) = self.to_screen_f64(rect.end_x.into(), rect.end_y.into());
+        outer_rect(start_x, start_y, end_x, end_y)
+    }
+}
+
+fn outer_rect(start_x: f64, start_y: f64, end_x: f64, end_y: f64) -> Rect {
+    Rect::new(
+        start_x.floor() as i32,
+        start_y.floor() as i32,
+        end_x.ceil() as i32,
+        end_y.ceil() as i32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewport() -> Viewport {
+        Viewport {
+            scroll_x: 37.5,
+            scroll_y: -120.25,
+            zoom: 1.7,
+            pixel_ratio: 2.0,
+        }
+    }
+
+    fn assert_near((x, y): (f64, f64), (expected_x, expected_y): (f64, f64)) {
+        assert!(
+            (x - expected_x).abs() < 1e-9 && (y - expected_y).abs() < 1e-9,
+            "({x}, {y}) != ({expected_x}, {expected_y})"
+        );
+    }
+
+    #[test]
+    fn screen_and_scene_round_trip() {
+        let viewport = viewport();
+        for (x, y) in [(0.0, 0.0), (-250.5, 13.0), (1024.0, 768.0)] {
+            let (screen_x, screen_y) = viewport.to_screen_f64(x, y);
+            assert_near(viewport.to_scene_f64(screen_x, screen_y), (x, y));
+        }
+        // 整数坐标在缩放大于 1 时取整后不变
+        for (x, y) in [(0, 0), (-250, 13), (1024, 768)] {
+            let (screen_x, screen_y) = viewport.to_screen_f64(x.into(), y.into());
+            let screen = (screen_x.round() as i32, screen_y.round() as i32);
+            assert_eq!(viewport.to_scene(screen.0, screen.1), (x, y));
+        }
+    }
+
+    #[test]
+    fn rects_are_covered_both_ways() {
+        let viewport = viewport();
+        let rect = Rect::new(-30, 40, 200, 90);
+        let screen = viewport.to_screen_rect(rect);
+        let (start_x, start_y) = viewport.to_screen_f64(-30.0, 40.0);
+        let (end_x, end_y) = viewport.to_screen_f64(200.0, 90.0);
+        assert!(screen.start_x as f64 <= start_x && screen.start_y as f64 <= start_y);
+        assert!(screen.end_x as f64 >= end_x && screen.end_y as f64 >= end_y);
+
+        let scene = viewport.to_scene_rect(screen);
+        assert!(scene.start_x <= rect.start_x && scene.start_y <= rect.start_y);
+        assert!(scene.end_x >= rect.end_x && scene.end_y >= rect.end_y);
+        // 反向的区域先取正
+        assert_eq!(viewport.to_screen_rect(Rect::new(200, 90, -30, 40)), screen);
+    }
+
+    #[test]
+    fn zoom_at_keeps_the_point_under_the_cursor() {
+        let mut viewport = viewport();
+        let (x, y) = (320.0, 240.0);
+        let before = viewport.to_scene_f64(x, y);
+        for zoom in [2.5, 0.4, 1.0, 12.0] {
+            viewport.zoom_at(x, y, zoom);
+            assert_eq!(viewport.zoom, zoom);
+            assert_near(viewport.to_scene_f64(x, y), before);
+        }
+    }
+
+    #[test]
+    fn zoom_is_clamped() {
+        let mut viewport = viewport();
+        let before = viewport.to_scene_f64(100.0, 100.0);
+        viewport.zoom_at(100.0, 100.0, 0.001);
+        assert_eq!(viewport.zoom, MIN_ZOOM);
+        assert_near(viewport.to_scene_f64(100.0, 100.0), before);
+        viewport.zoom_at(100.0, 100.0, 1000.0);
+        assert_eq!(viewport.zoom, MAX_ZOOM);
+        assert_near(viewport.to_scene_f64(100.0, 100.0), before);
+    }
+}
@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum WidgetKind {
+    #[default]
     Rectangle,
     Ellipse,
     Arrow,
@@ -20,9 +21,3 @@ impl std::fmt::Display for WidgetKind {
         }
     }
 }
-
-impl Default for WidgetKind {
-    fn default() -> Self {
-        Self::Rectangle
-    }
-}
@@ -0,0 +1,236 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+};
+
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+
+use crate::{
+    draw_scene::{draw_shapes, get_context},
+    model::{
+        element::Element,
+        id::ElementId,
+        primitive::{Op, Primitive},
+    },
+};
+
+/// Extra room around the paths for the stroke width and antialiasing.
+const PADDING: f64 = 2.0;
+/// Elements larger than this on screen are drawn directly, a bitmap that
+/// big would cost more memory than drawing saves time.
+const MAX_CACHE_SIZE: f64 = 4096.0;
+/// Pixels of all bitmaps together, 64 MB of RGBA. The bitmaps drawn least
+/// recently are dropped first.
+const MAX_CACHE_PIXELS: f64 = 16.0 * 1024.0 * 1024.0;
+/// Bitmaps are rendered at zoom levels this many steps per doubling, so a
+/// small zoom change reuses them instead of rendering everything again.
+const ZOOM_STEPS: f64 = 4.0;
+
+/// The local geometry of an element rendered at one zoom level.
+struct CachedElement {
+    version: u32,
+    version_nonce: u32,
+    zoom: f64,
+    canvas: HtmlCanvasElement,
+    /// Where the canvas starts relative to the element's origin.
+    offset_x: f64,
+    offset_y: f64,
+    width: f64,
+    height: f64,
+    /// When the bitmap was last drawn, in `Cache::clock` ticks.
+    last_used: u64,
+}
+
+impl CachedElement {
+    fn get_pixels(&self) -> f64 {
+        self.canvas.width() as f64 * self.canvas.height() as f64
+    }
+}
+
+#[derive(Default)]
+struct Cache {
+    elements: HashMap<ElementId, CachedElement>,
+    /// Total pixels of the bitmaps in `elements`.
+    pixels: f64,
+    clock: u64,
+}
+
+impl Cache {
+    fn insert(&mut self, id: ElementId, cached: CachedElement) {
+        self.remove(&id);
+        self.pixels += cached.get_pixels();
+        self.elements.insert(id, cached);
+        self.evict();
+    }
+
+    fn remove(&mut self, id: &ElementId) {
+        if let Some(cached) = self.elements.remove(id) {
+            self.pixels -= cached.get_pixels();
+        }
+    }
+
+    /**
+     * 超出总像素限制时，按最近一次绘制的时间从早到晚移除
+     */
+    fn evict(&mut self) {
+        if self.pixels <= MAX_CACHE_PIXELS {
+            return;
+        }
+        let mut ids: Vec<(u64, ElementId)> = self
+            .elements
+            .iter()
+            .map(|(id, cached)| (cached.last_used, id.clone()))
+            .collect();
+        ids.sort_unstable();
+        for (_, id) in ids {
+            if self.pixels <= MAX_CACHE_PIXELS {
+                break;
+            }
+            self.remove(&id);
+        }
+    }
+}
+
+thread_local! {
+    static CACHE: RefCell<Cache> = RefCell::new(Cache::default());
+}
+
+/**
+ * 绘制元素的局部几何，优先使用缓存的位图。需要在外面 save/restore，
+ * 并且已经平移到元素的原点（包括旋转）
+ */
+pub fn draw_cached_shapes(ctx: &CanvasRenderingContext2d, element: &Element, zoom: f64) {
+    let zoom = quantize_zoom(zoom);
+    let drawn = CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        cache.clock += 1;
+        let clock = cache.clock;
+        let is_fresh = cache.elements.get(&element.id).is_some_and(|cached| {
+            cached.version == element.version
+                && cached.version_nonce == element.version_nonce
+                && cached.zoom == zoom
+        });
+        if !is_fresh {
+            match render(element, zoom) {
+                Some(cached) => cache.insert(element.id.clone(), cached),
+                None => cache.remove(&element.id),
+            };
+        }
+        match cache.elements.get_mut(&element.id) {
+            Some(cached) => {
+                cached.last_used = clock;
+                ctx.draw_image_with_html_canvas_element_and_dw_and_dh(
+                    &cached.canvas,
+                    cached.offset_x,
+                    cached.offset_y,
+                    cached.width,
+                    cached.height,
+                )
+                .unwrap();
+                true
+            }
+            None => false,
+        }
+    });
+    if !drawn {
+        draw_shapes(ctx, element);
+    }
+}
+
+/**
+ * 只保留这些元素的缓存，全部重绘时传入屏幕上的元素
+ */
+pub fn retain(elements: &[&Element]) {
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let ids: HashSet<&ElementId> = elements.iter().map(|element| &element.id).collect();
+        let removed: Vec<ElementId> = cache
+            .elements
+            .keys()
+            .filter(|id| !ids.contains(id))
+            .cloned()
+            .collect();
+        removed.iter().for_each(|id| cache.remove(id));
+    });
+}
+
+/// Rounds the zoom up to the next step, so a bitmap is at most a step
+/// sharper than needed and never blurry.
+fn quantize_zoom(zoom: f64) -> f64 {
+    2f64.powf((zoom.log2() * ZOOM_STEPS).ceil() / ZOOM_STEPS)
+}
+
+fn render(element: &Element, zoom: f64) -> Option<CachedElement> {
+    let (min_x, min_y, max_x, max_y) = get_shapes_bounds(&element.shapes)?;
+    let padding = PADDING + element.get_stroke_width();
+    let (offset_x, offset_y) = (min_x - padding, min_y - padding);
+    let width = max_x - min_x + padding * 2.0;
+    let height = max_y - min_y + padding * 2.0;
+    let (pixel_width, pixel_height) = ((width * zoom).ceil(), (height * zoom).ceil());
+    if pixel_width > MAX_CACHE_SIZE || pixel_height > MAX_CACHE_SIZE {
+        return None;
+    }
+
+    let document = web_sys::window()?.document()?;
+    let canvas = document
+        .create_element("canvas")
+        .ok()?
+        .dyn_into::<HtmlCanvasElement>()
+        .ok()?;
+    canvas.set_width(pixel_width.max(1.0) as u32);
+    canvas.set_height(pixel_height.max(1.0) as u32);
+    let ctx = get_context(&canvas);
+    ctx.scale(zoom, zoom).unwrap();
+    ctx.translate(-offset_x, -offset_y).unwrap();
+    draw_shapes(&ctx, element);
+
+    Some(CachedElement {
+        version: element.version,
+        version_nonce: element.version_nonce,
+        zoom,
+        canvas,
+        offset_x,
+        offset_y,
+        width: pixel_width / zoom,
+        height: pixel_height / zoom,
+        last_used: 0,
+    })
+}
+
+/// Box around every point of the paths, control points included, so the
+/// curves are inside as well.
+fn get_shapes_bounds(shapes: &[Primitive]) -> Option<(f64, f64, f64, f64)> {
+    shapes
+        .iter()
+        .flat_map(|shape| match shape {
+            Primitive::Stroke { ops, .. } | Primitive::Fill { ops, .. } => ops.as_slice(),
+            Primitive::Text { .. } => &[],
+        })
+        .flat_map(|op| match *op {
+            Op::Move(x, y) | Op::LineTo(x, y) => vec![(x, y)],
+            Op::BCurveTo(cp1x, cp1y, cp2x, cp2y, x, y) => vec![(cp1x, cp1y), (cp2x, cp2y), (x, y)],
+        })
+        .fold(None, |bounds, (x, y)| match bounds {
+            None => Some((x, y, x, y)),
+            Some((min_x, min_y, max_x, max_y)) => {
+                Some((min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)))
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zoom_is_rounded_up_to_a_step() {
+        assert_eq!(quantize_zoom(1.0), 1.0);
+        assert_eq!(quantize_zoom(2.0), 2.0);
+        assert_eq!(quantize_zoom(1.01), quantize_zoom(1.1));
+        for zoom in [0.1, 0.37, 0.9, 1.3, 2.5, 7.0, 30.0] {
+            let quantized = quantize_zoom(zoom);
+            assert!(quantized >= zoom && quantized < zoom * 2f64.powf(1.0 / ZOOM_STEPS) + 1e-9);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+
+use super::random::Random;
+
+pub type Point = (f64, f64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Op {
+    Move(f64, f64),
+    LineTo(f64, f64),
+    BCurveTo(f64, f64, f64, f64, f64, f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OpSetKind {
+    /// Outline, stroked with the stroke color.
+    Path,
+    /// Closed area, filled with the fill color.
+    FillPath,
+    /// Hachure lines, stroked with the fill color.
+    FillSketch,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpSet {
+    pub kind: OpSetKind,
+    pub ops: Vec<Op>,
+}
+
+impl OpSet {
+    pub fn new(kind: OpSetKind, ops: Vec<Op>) -> OpSet {
+        OpSet { kind, ops }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum FillStyle {
+    #[default]
+    Hachure,
+    CrossHatch,
+    Solid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Options {
+    pub max_randomness_offset: f64,
+    pub roughness: f64,
+    pub bowing: f64,
+    pub stroke: String,
+    pub stroke_width: f64,
+    pub curve_fitting: f64,
+    pub curve_tightness: f64,
+    pub curve_step_count: f64,
+    pub fill: Option<String>,
+    pub fill_style: FillStyle,
+    /// Width of hachure lines, negative means half of `stroke_width`.
+    pub fill_weight: f64,
+    /// Angle of hachure lines in degrees.
+    pub hachure_angle: f64,
+    /// Gap between hachure lines, negative means four times `stroke_width`.
+    pub hachure_gap: f64,
+    pub seed: u32,
+    pub disable_multi_stroke: bool,
+    pub disable_multi_stroke_fill: bool,
+    pub preserve_vertices: bool,
+    /// Seeded from `seed` on first use.
+    #[serde(skip)]
+    pub randomizer: Option<Random>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            max_randomness_offset: 2.0,
+            roughness: 1.0,
+            bowing: 1.0,
+            stroke: "#000".into(),
+            stroke_width: 1.0,
+            curve_fitting: 0.95,
+            curve_tightness: 0.0,
+            curve_step_count: 9.0,
+            fill: None,
+            fill_style: FillStyle::Hachure,
+            fill_weight: -1.0,
+            hachure_angle: -41.0,
+            hachure_gap: -1.0,
+            seed: 1,
+            disable_multi_stroke: false,
+            disable_multi_stroke_fill: false,
+            preserve_vertices: false,
+            randomizer: None,
+        }
+    }
+}
+
+impl Options {
+    pub fn random(&mut self) -> f64 {
+        let seed = self.seed;
+        self.randomizer
+            .get_or_insert_with(|| Random::new(seed))
+            .next_float()
+    }
+
+    /// Same options with a fresh randomizer one seed further along.
+    pub fn with_next_seed(&self) -> Options {
+        Options {
+            seed: self.seed.wrapping_add(1),
+            randomizer: None,
+            ..self.clone()
+        }
+    }
+
+    pub fn get_fill_weight(&self) -> f64 {
+        if self.fill_weight < 0.0 {
+            self.stroke_width / 2.0
+        } else {
+            self.fill_weight
+        }
+    }
+
+    pub fn get_hachure_gap(&self) -> f64 {
+        let gap = if self.hachure_gap < 0.0 {
+            self.stroke_width * 4.0
+        } else {
+            self.hachure_gap
+        };
+        gap.max(0.1)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Drawable {
+    pub options: Options,
+    pub sets: Vec<OpSet>,
+}
@@ -0,0 +1,156 @@
+//! Hachure and cross-hatch fills, drawn as sketchy lines clipped to a polygon.
+
+use super::{
+    core::{FillStyle, OpSet, OpSetKind, Options, Point},
+    renderer::double_line,
+};
+
+type Line = (Point, Point);
+
+pub fn pattern_fill_polygons(polygons: &[Vec<Point>], o: &mut Options) -> OpSet {
+    let mut lines = hachure_lines(polygons, o.hachure_angle, o.get_hachure_gap());
+    if o.fill_style == FillStyle::CrossHatch {
+        lines.extend(hachure_lines(
+            polygons,
+            o.hachure_angle + 90.0,
+            o.get_hachure_gap(),
+        ));
+    }
+    let mut ops = Vec::new();
+    for ((x1, y1), (x2, y2)) in lines {
+        ops.extend(double_line(x1, y1, x2, y2, o, true));
+    }
+    OpSet::new(OpSetKind::FillSketch, ops)
+}
+
+/// Rotates the polygons so the hachure becomes horizontal, scans them with
+/// lines `gap` apart and rotates the resulting segments back.
+fn hachure_lines(polygons: &[Vec<Point>], angle: f64, gap: f64) -> Vec<Line> {
+    let angle = (angle + 90.0).to_radians();
+    let rotated: Vec<Vec<Point>> = polygons
+        .iter()
+        .map(|points| points.iter().map(|&p| rotate(p, angle)).collect())
+        .collect();
+    scan_lines(&rotated, gap)
+        .into_iter()
+        .map(|(p1, p2)| (rotate(p1, -angle), rotate(p2, -angle)))
+        .collect()
+}
+
+fn scan_lines(polygons: &[Vec<Point>], gap: f64) -> Vec<Line> {
+    let edges: Vec<Line> = polygons
+        .iter()
+        .filter(|points| points.len() > 2)
+        .flat_map(|points| {
+            points
+                .iter()
+                .zip(points.iter().cycle().skip(1))
+                .map(|(&p1, &p2)| (p1, p2))
+        })
+        .filter(|((_, y1), (_, y2))| y1 != y2)
+        .collect();
+    if edges.is_empty() {
+        return Vec::new();
+    }
+
+    let min_y = edges
+        .iter()
+        .map(|((_, y1), (_, y2))| y1.min(*y2))
+        .fold(f64::MAX, f64::min);
+    let max_y = edges
+        .iter()
+        .map(|((_, y1), (_, y2))| y1.max(*y2))
+        .fold(f64::MIN, f64::max);
+
+    let mut lines = Vec::new();
+    let mut y = min_y + gap / 2.0;
+    while y < max_y {
+        let mut xs: Vec<f64> = edges
+            .iter()
+            .filter(|((_, y1), (_, y2))| y1.min(*y2) <= y && y < y1.max(*y2))
+            .map(|((x1, y1), (x2, y2))| x1 + (y - y1) * (x2 - x1) / (y2 - y1))
+            .collect();
+        xs.sort_by(|a, b| a.total_cmp(b));
+        for pair in xs.chunks_exact(2) {
+            lines.push(((pair[0], y), (pair[1], y)));
+        }
+        y += gap;
+    }
+    lines
+}
+
+fn rotate((x, y): Point, angle: f64) -> Point {
+    let (sin, cos) = angle.sin_cos();
+    (x * cos - y * sin, x * sin + y * cos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rough::core::Op;
+
+    fn square(size: f64) -> Vec<Point> {
+        vec![(0.0, 0.0), (size, 0.0), (size, size), (0.0, size)]
+    }
+
+    #[test]
+    fn scan_lines_are_inside_and_gap_apart() {
+        let lines = scan_lines(&[square(100.0)], 10.0);
+        assert_eq!(lines.len(), 10);
+        for (i, ((x1, y1), (x2, y2))) in lines.iter().enumerate() {
+            assert_eq!(y1, y2);
+            assert_eq!(*y1, 5.0 + 10.0 * i as f64);
+            assert_eq!((*x1, *x2), (0.0, 100.0));
+        }
+    }
+
+    #[test]
+    fn degenerate_polygons_have_no_lines() {
+        assert!(scan_lines(&[vec![(0.0, 0.0), (10.0, 10.0)]], 1.0).is_empty());
+        assert!(scan_lines(&[vec![(0.0, 0.0), (10.0, 0.0), (20.0, 0.0)]], 1.0).is_empty());
+    }
+
+    #[test]
+    fn hachure_lines_stay_inside() {
+        let lines = hachure_lines(&[square(100.0)], -41.0, 10.0);
+        assert!(!lines.is_empty());
+        for ((x1, y1), (x2, y2)) in lines {
+            for (x, y) in [(x1, y1), (x2, y2)] {
+                assert!((-1e-6..=100.0 + 1e-6).contains(&x));
+                assert!((-1e-6..=100.0 + 1e-6).contains(&y));
+            }
+        }
+    }
+
+    #[test]
+    fn cross_hatch_doubles_the_lines() {
+        let mut o = Options {
+            hachure_gap: 10.0,
+            seed: 2,
+            ..Default::default()
+        };
+        let moves = |set: OpSet| {
+            set.ops
+                .iter()
+                .filter(|op| matches!(op, Op::Move(..)))
+                .count()
+        };
+        let hachure = moves(pattern_fill_polygons(&[square(100.0)], &mut o));
+        o.fill_style = FillStyle::CrossHatch;
+        let cross_hatch = moves(pattern_fill_polygons(&[square(100.0)], &mut o));
+        assert_eq!(cross_hatch, hachure * 2);
+    }
+
+    #[test]
+    fn fill_is_seeded() {
+        let fill = |seed| {
+            let mut o = Options {
+                seed,
+                ..Default::default()
+            };
+            pattern_fill_polygons(&[square(50.0)], &mut o)
+        };
+        assert_eq!(fill(8), fill(8));
+        assert_ne!(fill(8), fill(9));
+    }
+}
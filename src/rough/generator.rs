@@ -0,0 +1,151 @@
+//! Builds `Drawable`s the way `rough.generator()` does: an outline plus an
+//! optional fill, all randomized from `Options::seed`.
+
+use super::{
+    core::{Drawable, FillStyle, OpSetKind, Options, Point},
+    filler::pattern_fill_polygons,
+    renderer,
+};
+
+pub fn line(x1: f64, y1: f64, x2: f64, y2: f64, mut o: Options) -> Drawable {
+    let sets = vec![renderer::line(x1, y1, x2, y2, &mut o)];
+    Drawable { options: o, sets }
+}
+
+pub fn linear_path(points: &[Point], mut o: Options) -> Drawable {
+    let sets = vec![renderer::linear_path(points, false, &mut o)];
+    Drawable { options: o, sets }
+}
+
+pub fn rectangle(x: f64, y: f64, width: f64, height: f64, o: Options) -> Drawable {
+    let points = [
+        (x, y),
+        (x + width, y),
+        (x + width, y + height),
+        (x, y + height),
+    ];
+    polygon(&points, o)
+}
+
+pub fn polygon(points: &[Point], mut o: Options) -> Drawable {
+    let outline = renderer::polygon(points, &mut o);
+    let mut sets = Vec::new();
+    if o.fill.is_some() {
+        sets.push(fill_polygon(points.to_vec(), &mut o));
+    }
+    sets.push(outline);
+    Drawable { options: o, sets }
+}
+
+pub fn ellipse(center_x: f64, center_y: f64, width: f64, height: f64, mut o: Options) -> Drawable {
+    let params = renderer::generate_ellipse_params(width, height, &mut o);
+    let (outline, estimated_points) =
+        renderer::ellipse_with_params(center_x, center_y, &mut o, &params);
+    let mut sets = Vec::new();
+    if o.fill.is_some() {
+        if o.fill_style == FillStyle::Solid {
+            let (mut shape, _) = renderer::ellipse_with_params(center_x, center_y, &mut o, &params);
+            shape.kind = OpSetKind::FillPath;
+            sets.push(shape);
+        } else {
+            sets.push(pattern_fill_polygons(&[estimated_points], &mut o));
+        }
+    }
+    sets.push(outline);
+    Drawable { options: o, sets }
+}
+
+pub fn curve(points: &[Point], mut o: Options) -> Drawable {
+    let outline = renderer::curve(points, &mut o);
+    let mut sets = Vec::new();
+    if o.fill.is_some() && points.len() >= 3 {
+        sets.push(fill_polygon(points.to_vec(), &mut o));
+    }
+    sets.push(outline);
+    Drawable { options: o, sets }
+}
+
+fn fill_polygon(points: Vec<Point>, o: &mut Options) -> super::core::OpSet {
+    if o.fill_style == FillStyle::Solid {
+        renderer::solid_fill_polygon(&[points], o)
+    } else {
+        pattern_fill_polygons(&[points], o)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(seed: u32, fill: Option<&str>, fill_style: FillStyle) -> Options {
+        Options {
+            seed,
+            fill: fill.map(String::from),
+            fill_style,
+            ..Default::default()
+        }
+    }
+
+    fn kinds(drawable: &Drawable) -> Vec<OpSetKind> {
+        drawable.sets.iter().map(|set| set.kind).collect()
+    }
+
+    #[test]
+    fn same_seed_same_shape() {
+        let o = || options(12, Some("#ff0"), FillStyle::Hachure);
+        assert_eq!(
+            rectangle(0.0, 0.0, 80.0, 40.0, o()).sets,
+            rectangle(0.0, 0.0, 80.0, 40.0, o()).sets
+        );
+        assert_eq!(
+            ellipse(40.0, 20.0, 80.0, 40.0, o()).sets,
+            ellipse(40.0, 20.0, 80.0, 40.0, o()).sets
+        );
+    }
+
+    #[test]
+    fn different_seed_different_shape() {
+        let o = |seed| options(seed, None, FillStyle::Hachure);
+        assert_ne!(
+            rectangle(0.0, 0.0, 80.0, 40.0, o(1)).sets,
+            rectangle(0.0, 0.0, 80.0, 40.0, o(2)).sets
+        );
+        assert_ne!(
+            line(0.0, 0.0, 80.0, 40.0, o(1)).sets,
+            line(0.0, 0.0, 80.0, 40.0, o(2)).sets
+        );
+    }
+
+    #[test]
+    fn fill_comes_before_outline() {
+        let outline = rectangle(0.0, 0.0, 80.0, 40.0, options(1, None, FillStyle::Hachure));
+        assert_eq!(kinds(&outline), [OpSetKind::Path]);
+
+        let hachure = rectangle(
+            0.0,
+            0.0,
+            80.0,
+            40.0,
+            options(1, Some("#f00"), FillStyle::Hachure),
+        );
+        assert_eq!(kinds(&hachure), [OpSetKind::FillSketch, OpSetKind::Path]);
+
+        let solid = ellipse(
+            0.0,
+            0.0,
+            80.0,
+            40.0,
+            options(1, Some("#f00"), FillStyle::Solid),
+        );
+        assert_eq!(kinds(&solid), [OpSetKind::FillPath, OpSetKind::Path]);
+    }
+
+    #[test]
+    fn curve_needs_three_points_to_fill() {
+        let o = || options(1, Some("#f00"), FillStyle::Solid);
+        let two = curve(&[(0.0, 0.0), (10.0, 10.0)], o());
+        assert_eq!(kinds(&two), [OpSetKind::Path]);
+        let three = curve(&[(0.0, 0.0), (10.0, 10.0), (20.0, 0.0)], o());
+        assert_eq!(kinds(&three), [OpSetKind::FillPath, OpSetKind::Path]);
+    }
+}
@@ -0,0 +1,74 @@
+use self::core::{Drawable, Options};
+
+pub mod core;
+pub mod filler;
+pub mod generator;
+pub mod random;
+pub mod renderer;
+
+pub struct Rough {}
+
+impl Rough {
+    pub fn generator_rectangle(
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        item_stroke_color: String,
+        item_bg_color: String,
+        seed: u32,
+    ) -> Drawable {
+        generator::rectangle(
+            x.into(),
+            y.into(),
+            w.into(),
+            h.into(),
+            Rough::options(item_stroke_color, item_bg_color, seed),
+        )
+    }
+
+    pub fn generator_ellipse(
+        center_x: i32,
+        center_y: i32,
+        w: i32,
+        h: i32,
+        item_stroke_color: String,
+        item_bg_color: String,
+        seed: u32,
+    ) -> Drawable {
+        generator::ellipse(
+            center_x.into(),
+            center_y.into(),
+            w.into(),
+            h.into(),
+            Rough::options(item_stroke_color, item_bg_color, seed),
+        )
+    }
+
+    pub fn generator_line(
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+        item_stroke_color: String,
+        item_bg_color: String,
+        seed: u32,
+    ) -> Drawable {
+        generator::line(
+            x1.into(),
+            y1.into(),
+            x2.into(),
+            y2.into(),
+            Rough::options(item_stroke_color, item_bg_color, seed),
+        )
+    }
+
+    fn options(item_stroke_color: String, item_bg_color: String, seed: u32) -> Options {
+        Options {
+            stroke: item_stroke_color,
+            fill: Some(item_bg_color),
+            seed,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,60 @@
+/// Park-Miller LCG, the same one rough.js falls back to when a `seed` option is
+/// given, so a seed always produces the same wobble.
+#[derive(Debug, Clone, Copy)]
+pub struct Random {
+    seed: u32,
+}
+
+impl Random {
+    pub fn new(seed: u32) -> Random {
+        // A zero seed would stay zero forever.
+        Random { seed: seed.max(1) }
+    }
+
+    /// Returns a number in `[0, 1)`.
+    pub fn next_float(&mut self) -> f64 {
+        self.seed = self.seed.wrapping_mul(48271);
+        (self.seed & 0x7fff_ffff) as f64 / 2f64.powi(31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Random;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = Random::new(42);
+        let mut b = Random::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.next_float(), b.next_float());
+        }
+    }
+
+    #[test]
+    fn different_seeds_differ() {
+        let a: Vec<f64> = (0..10)
+            .scan(Random::new(1), |r, _| Some(r.next_float()))
+            .collect();
+        let b: Vec<f64> = (0..10)
+            .scan(Random::new(2), |r, _| Some(r.next_float()))
+            .collect();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn values_in_unit_range() {
+        let mut random = Random::new(7);
+        for _ in 0..10_000 {
+            let value = random.next_float();
+            assert!((0.0..1.0).contains(&value));
+        }
+    }
+
+    #[test]
+    fn zero_seed_does_not_stick() {
+        let mut random = Random::new(0);
+        assert_ne!(random.next_float(), 0.0);
+        assert_eq!(Random::new(0).next_float(), Random::new(1).next_float());
+    }
+}
@@ -0,0 +1,396 @@
+//! Port of the sketchy geometry from rough.js (`renderer.ts`).
+
+use std::f64::consts::PI;
+
+use super::core::{Op, OpSet, OpSetKind, Options, Point};
+
+pub struct EllipseParams {
+    pub rx: f64,
+    pub ry: f64,
+    pub increment: f64,
+}
+
+pub fn line(x1: f64, y1: f64, x2: f64, y2: f64, o: &mut Options) -> OpSet {
+    OpSet::new(OpSetKind::Path, double_line(x1, y1, x2, y2, o, false))
+}
+
+pub fn linear_path(points: &[Point], close: bool, o: &mut Options) -> OpSet {
+    let len = points.len();
+    if len > 2 {
+        let mut ops = Vec::new();
+        for i in 0..len - 1 {
+            let (x1, y1) = points[i];
+            let (x2, y2) = points[i + 1];
+            ops.extend(double_line(x1, y1, x2, y2, o, false));
+        }
+        if close {
+            let (x1, y1) = points[len - 1];
+            let (x2, y2) = points[0];
+            ops.extend(double_line(x1, y1, x2, y2, o, false));
+        }
+        OpSet::new(OpSetKind::Path, ops)
+    } else if len == 2 {
+        let (x1, y1) = points[0];
+        let (x2, y2) = points[1];
+        line(x1, y1, x2, y2, o)
+    } else {
+        OpSet::new(OpSetKind::Path, Vec::new())
+    }
+}
+
+pub fn polygon(points: &[Point], o: &mut Options) -> OpSet {
+    linear_path(points, true, o)
+}
+
+pub fn curve(points: &[Point], o: &mut Options) -> OpSet {
+    let roughness = o.roughness;
+    let mut ops = curve_with_offset(points, 1.0 * (1.0 + roughness * 0.2), o);
+    if !o.disable_multi_stroke {
+        let mut o2 = o.with_next_seed();
+        ops.extend(curve_with_offset(
+            points,
+            1.5 * (1.0 + roughness * 0.22),
+            &mut o2,
+        ));
+    }
+    OpSet::new(OpSetKind::Path, ops)
+}
+
+pub fn generate_ellipse_params(width: f64, height: f64, o: &mut Options) -> EllipseParams {
+    let psq = (PI * 2.0 * (((width / 2.0).powi(2) + (height / 2.0).powi(2)) / 2.0).sqrt()).sqrt();
+    let step_count = o
+        .curve_step_count
+        .max((o.curve_step_count / 200f64.sqrt()) * psq)
+        .ceil();
+    let increment = (PI * 2.0) / step_count;
+    let mut rx = (width / 2.0).abs();
+    let mut ry = (height / 2.0).abs();
+    let curve_fit_randomness = 1.0 - o.curve_fitting;
+    rx += offset_opt(rx * curve_fit_randomness, o, 1.0);
+    ry += offset_opt(ry * curve_fit_randomness, o, 1.0);
+    EllipseParams { rx, ry, increment }
+}
+
+/// Returns the outline together with the points it was fitted through, which
+/// the pattern fillers use as the ellipse polygon.
+pub fn ellipse_with_params(
+    x: f64,
+    y: f64,
+    o: &mut Options,
+    params: &EllipseParams,
+) -> (OpSet, Vec<Point>) {
+    let overlap = params.increment * offset(0.1, offset(0.4, 1.0, o, 1.0), o, 1.0);
+    let (all_points, core_points) = compute_ellipse_points(
+        params.increment,
+        x,
+        y,
+        params.rx,
+        params.ry,
+        1.0,
+        overlap,
+        o,
+    );
+    let mut ops = curve_ops(&all_points, None, o);
+    if !o.disable_multi_stroke && o.roughness != 0.0 {
+        let (all_points, _) =
+            compute_ellipse_points(params.increment, x, y, params.rx, params.ry, 1.5, 0.0, o);
+        ops.extend(curve_ops(&all_points, None, o));
+    }
+    (OpSet::new(OpSetKind::Path, ops), core_points)
+}
+
+pub fn solid_fill_polygon(polygons: &[Vec<Point>], o: &mut Options) -> OpSet {
+    let mut ops = Vec::new();
+    for points in polygons.iter().filter(|points| points.len() > 2) {
+        let offset = o.max_randomness_offset;
+        let (x, y) = points[0];
+        ops.push(Op::Move(
+            x + offset_opt(offset, o, 1.0),
+            y + offset_opt(offset, o, 1.0),
+        ));
+        for &(x, y) in &points[1..] {
+            ops.push(Op::LineTo(
+                x + offset_opt(offset, o, 1.0),
+                y + offset_opt(offset, o, 1.0),
+            ));
+        }
+    }
+    OpSet::new(OpSetKind::FillPath, ops)
+}
+
+pub fn double_line(x1: f64, y1: f64, x2: f64, y2: f64, o: &mut Options, filling: bool) -> Vec<Op> {
+    let single_stroke = if filling {
+        o.disable_multi_stroke_fill
+    } else {
+        o.disable_multi_stroke
+    };
+    let mut ops = single_line(x1, y1, x2, y2, o, true, false);
+    if !single_stroke {
+        ops.extend(single_line(x1, y1, x2, y2, o, true, true));
+    }
+    ops
+}
+
+fn offset(min: f64, max: f64, o: &mut Options, roughness_gain: f64) -> f64 {
+    o.roughness * roughness_gain * (o.random() * (max - min) + min)
+}
+
+fn offset_opt(x: f64, o: &mut Options, roughness_gain: f64) -> f64 {
+    offset(-x, x, o, roughness_gain)
+}
+
+fn single_line(
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+    o: &mut Options,
+    move_to: bool,
+    overlay: bool,
+) -> Vec<Op> {
+    let length_sq = (x1 - x2).powi(2) + (y1 - y2).powi(2);
+    let length = length_sq.sqrt();
+    let roughness_gain = if length < 200.0 {
+        1.0
+    } else if length > 500.0 {
+        0.4
+    } else {
+        -0.0016668 * length + 1.233334
+    };
+
+    let mut offset = o.max_randomness_offset;
+    if offset * offset * 100.0 > length_sq {
+        offset = length / 10.0;
+    }
+    let half_offset = offset / 2.0;
+    let diverge = 0.2 + o.random() * 0.2;
+    let mid_disp_x = o.bowing * o.max_randomness_offset * (y2 - y1) / 200.0;
+    let mid_disp_y = o.bowing * o.max_randomness_offset * (x1 - x2) / 200.0;
+    let mid_disp_x = offset_opt(mid_disp_x, o, roughness_gain);
+    let mid_disp_y = offset_opt(mid_disp_y, o, roughness_gain);
+
+    let jitter = if overlay { half_offset } else { offset };
+    let preserve_vertices = o.preserve_vertices;
+    let vertex = |o: &mut Options| {
+        if preserve_vertices {
+            0.0
+        } else {
+            offset_opt(jitter, o, roughness_gain)
+        }
+    };
+
+    let mut ops = Vec::new();
+    if move_to {
+        let x = x1 + vertex(o);
+        let y = y1 + vertex(o);
+        ops.push(Op::Move(x, y));
+    }
+    let cp1x = mid_disp_x + x1 + (x2 - x1) * diverge + offset_opt(jitter, o, roughness_gain);
+    let cp1y = mid_disp_y + y1 + (y2 - y1) * diverge + offset_opt(jitter, o, roughness_gain);
+    let cp2x = mid_disp_x + x1 + 2.0 * (x2 - x1) * diverge + offset_opt(jitter, o, roughness_gain);
+    let cp2y = mid_disp_y + y1 + 2.0 * (y2 - y1) * diverge + offset_opt(jitter, o, roughness_gain);
+    let x = x2 + vertex(o);
+    let y = y2 + vertex(o);
+    ops.push(Op::BCurveTo(cp1x, cp1y, cp2x, cp2y, x, y));
+    ops
+}
+
+fn curve_with_offset(points: &[Point], offset: f64, o: &mut Options) -> Vec<Op> {
+    if points.is_empty() {
+        return Vec::new();
+    }
+    let jitter = |(x, y): Point, o: &mut Options| {
+        (
+            x + offset_opt(offset, o, 1.0),
+            y + offset_opt(offset, o, 1.0),
+        )
+    };
+    let mut ps = vec![jitter(points[0], o), jitter(points[0], o)];
+    for (i, &point) in points.iter().enumerate().skip(1) {
+        ps.push(jitter(point, o));
+        if i == points.len() - 1 {
+            ps.push(jitter(point, o));
+        }
+    }
+    curve_ops(&ps, None, o)
+}
+
+/// Catmull-Rom spline through `points`, converted to cubic béziers.
+fn curve_ops(points: &[Point], close_point: Option<Point>, o: &mut Options) -> Vec<Op> {
+    let len = points.len();
+    let mut ops = Vec::new();
+    if len > 3 {
+        let s = 1.0 - o.curve_tightness;
+        ops.push(Op::Move(points[1].0, points[1].1));
+        let mut i = 1;
+        while i + 2 < len {
+            let (x0, y0) = points[i - 1];
+            let (x1, y1) = points[i];
+            let (x2, y2) = points[i + 1];
+            let (x3, y3) = points[i + 2];
+            ops.push(Op::BCurveTo(
+                x1 + (s * x2 - s * x0) / 6.0,
+                y1 + (s * y2 - s * y0) / 6.0,
+                x2 + (s * x1 - s * x3) / 6.0,
+                y2 + (s * y1 - s * y3) / 6.0,
+                x2,
+                y2,
+            ));
+            i += 1;
+        }
+        if let Some((x, y)) = close_point {
+            let ro = o.max_randomness_offset;
+            ops.push(Op::LineTo(
+                x + offset_opt(ro, o, 1.0),
+                y + offset_opt(ro, o, 1.0),
+            ));
+        }
+    } else if len == 3 {
+        let (x1, y1) = points[1];
+        let (x2, y2) = points[2];
+        ops.push(Op::Move(x1, y1));
+        ops.push(Op::BCurveTo(x1, y1, x2, y2, x2, y2));
+    } else if len == 2 {
+        let (x1, y1) = points[0];
+        let (x2, y2) = points[1];
+        ops.extend(double_line(x1, y1, x2, y2, o, false));
+    }
+    ops
+}
+
+#[allow(clippy::too_many_arguments)]
+fn compute_ellipse_points(
+    increment: f64,
+    cx: f64,
+    cy: f64,
+    rx: f64,
+    ry: f64,
+    offset: f64,
+    overlap: f64,
+    o: &mut Options,
+) -> (Vec<Point>, Vec<Point>) {
+    let mut core_points = Vec::new();
+    let mut all_points = Vec::new();
+
+    if o.roughness == 0.0 {
+        let increment = increment / 4.0;
+        all_points.push((cx + rx * (-increment).cos(), cy + ry * (-increment).sin()));
+        let mut angle = 0.0;
+        while angle <= PI * 2.0 {
+            let point = (cx + rx * angle.cos(), cy + ry * angle.sin());
+            core_points.push(point);
+            all_points.push(point);
+            angle += increment;
+        }
+        all_points.push((cx + rx, cy));
+        all_points.push((cx + rx * increment.cos(), cy + ry * increment.sin()));
+    } else {
+        let rad_offset = offset_opt(0.5, o, 1.0) - PI / 2.0;
+        let point_at = |scale: f64, angle: f64, o: &mut Options| {
+            (
+                offset_opt(offset, o, 1.0) + cx + scale * rx * angle.cos(),
+                offset_opt(offset, o, 1.0) + cy + scale * ry * angle.sin(),
+            )
+        };
+        all_points.push(point_at(0.9, rad_offset - increment, o));
+        let end_angle = PI * 2.0 + rad_offset - 0.01;
+        let mut angle = rad_offset;
+        while angle < end_angle {
+            let point = point_at(1.0, angle, o);
+            core_points.push(point);
+            all_points.push(point);
+            angle += increment;
+        }
+        all_points.push(point_at(1.0, rad_offset + PI * 2.0 + overlap * 0.5, o));
+        all_points.push(point_at(0.98, rad_offset + overlap, o));
+        all_points.push(point_at(0.9, rad_offset + overlap * 0.5, o));
+    }
+    (all_points, core_points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(seed: u32) -> Options {
+        Options {
+            seed,
+            ..Default::default()
+        }
+    }
+
+    fn end_point(op: &Op) -> Point {
+        match *op {
+            Op::Move(x, y) | Op::LineTo(x, y) | Op::BCurveTo(_, _, _, _, x, y) => (x, y),
+        }
+    }
+
+    #[test]
+    fn line_is_seeded() {
+        let a = line(0.0, 0.0, 100.0, 50.0, &mut options(3));
+        let b = line(0.0, 0.0, 100.0, 50.0, &mut options(3));
+        let c = line(0.0, 0.0, 100.0, 50.0, &mut options(4));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn double_line_stays_near_its_ends() {
+        let mut o = options(9);
+        let ops = double_line(10.0, 20.0, 210.0, 20.0, &mut o, false);
+        // Two strokes, each a move and a curve
+        assert_eq!(ops.len(), 4);
+        let max = o.max_randomness_offset * o.roughness;
+        for (start, end) in [(&ops[0], &ops[1]), (&ops[2], &ops[3])] {
+            let (x, y) = end_point(start);
+            assert!((x - 10.0).abs() <= max && (y - 20.0).abs() <= max);
+            let (x, y) = end_point(end);
+            assert!((x - 210.0).abs() <= max && (y - 20.0).abs() <= max);
+        }
+    }
+
+    #[test]
+    fn single_stroke_when_multi_stroke_disabled() {
+        let mut o = Options {
+            disable_multi_stroke: true,
+            ..options(1)
+        };
+        assert_eq!(double_line(0.0, 0.0, 50.0, 50.0, &mut o, false).len(), 2);
+        assert_eq!(double_line(0.0, 0.0, 50.0, 50.0, &mut o, true).len(), 4);
+    }
+
+    #[test]
+    fn zero_roughness_is_exact() {
+        let mut o = Options {
+            roughness: 0.0,
+            ..options(5)
+        };
+        let ops = line(0.0, 0.0, 100.0, 0.0, &mut o).ops;
+        assert_eq!(end_point(&ops[0]), (0.0, 0.0));
+        assert_eq!(end_point(&ops[1]), (100.0, 0.0));
+    }
+
+    #[test]
+    fn linear_path_needs_two_points() {
+        let mut o = options(1);
+        assert!(linear_path(&[], false, &mut o).ops.is_empty());
+        assert!(linear_path(&[(1.0, 1.0)], false, &mut o).ops.is_empty());
+        let square = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        // Each side is a double line of two ops per stroke
+        assert_eq!(linear_path(&square, false, &mut o).ops.len(), 3 * 4);
+        assert_eq!(polygon(&square, &mut o).ops.len(), 4 * 4);
+    }
+
+    #[test]
+    fn ellipse_points_follow_the_radii() {
+        let mut o = options(11);
+        let params = generate_ellipse_params(200.0, 100.0, &mut o);
+        let (outline, points) = ellipse_with_params(50.0, 50.0, &mut o, &params);
+        assert!(!outline.ops.is_empty());
+        assert!(points.len() >= o.curve_step_count as usize);
+        for (x, y) in points {
+            let distance = ((x - 50.0) / params.rx).powi(2) + ((y - 50.0) / params.ry).powi(2);
+            assert!((distance.sqrt() - 1.0).abs() < 0.05);
+        }
+    }
+}
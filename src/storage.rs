@@ -56,8 +56,9 @@ pub fn read_elements_from_clipboard() -> Option<Vec<Element>> {
         .expect("should get item")
         .unwrap_or_default();
     if data.is_empty() {
-        None
-    } else {
-        serde_json::from_str(&data).expect("should deserialize")
+        return None;
     }
+    let mut elements: Vec<Element> = serde_json::from_str(&data).expect("should deserialize");
+    elements.iter_mut().for_each(Element::migrate);
+    Some(elements)
 }
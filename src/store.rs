@@ -1,12 +1,30 @@
 use sycamore::reactive::{Modify, RcSignal};
 
-use crate::model::{widget_kind::WidgetKind, AppData};
+use crate::{
+    model::{
+        bound_text::{is_container, layout_bound_text},
+        element::{Element, ElementConfig},
+        font::{FontFamily, TextAlign},
+        id::ElementId,
+        primitive::Primitive,
+        rect::Rect,
+        scene::Scene,
+        viewport::Viewport,
+        widget_kind::WidgetKind,
+        AppData,
+    },
+    text_layout::measure_text,
+};
 
 #[derive(Debug, Clone)]
 pub struct ExportConfig {
     pub background: bool,
     pub visible_area_only: bool,
+    /// Export the selected elements instead of the whole scene.
+    pub selected_only: bool,
     pub padding: u32,
+    /// Pixel ratio of exported png images.
+    pub scale: u32,
 }
 
 impl Default for ExportConfig {
@@ -14,11 +32,30 @@ impl Default for ExportConfig {
         Self {
             background: false,
             visible_area_only: true,
+            selected_only: false,
             padding: 10,
+            scale: 1,
         }
     }
 }
 
+/// Text open in the editor over the canvas.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEditorState {
+    /// The text element being edited, `None` while writing a new one.
+    pub id: Option<ElementId>,
+    /// The rectangle or ellipse the text is the label of.
+    pub container_id: Option<ElementId>,
+    /// Top left corner of free text in scene coordinates.
+    pub x: i32,
+    pub y: i32,
+    pub angle: f64,
+    /// Color and font of the text.
+    pub config: ElementConfig,
+    /// What is typed in the editor so far.
+    pub text: String,
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct AppState {
     pub selected_kind: RcSignal<WidgetKind>,
@@ -26,7 +63,15 @@ pub struct AppState {
     pub view_bg_color: RcSignal<String>,
     pub item_stroke_color: RcSignal<String>,
     pub item_bg_color: RcSignal<String>,
+    /// Font of new text elements.
+    pub font_family: RcSignal<FontFamily>,
+    pub font_size: RcSignal<f64>,
+    pub text_align: RcSignal<TextAlign>,
     pub app_data: RcSignal<AppData>,
+    pub viewport: RcSignal<Viewport>,
+    /// Size of the main canvas in css pixels.
+    pub canvas_size: RcSignal<(f64, f64)>,
+    pub text_editor: RcSignal<Option<TextEditorState>>,
 }
 
 impl AppState {
@@ -40,15 +85,17 @@ impl AppState {
 
     pub fn delete_selected_elements(&self) {
         let mut app_data = self.get_data();
+        app_data.begin_change();
         app_data.delete_selected_elements();
-        app_data.draw();
+        app_data.end_change();
+        app_data.draw(&self.viewport.get());
         app_data.save_to_local_storage();
     }
 
     pub fn clear_selection_elements(&self) {
         let mut app_data = self.get_data();
         app_data.clean_selected_state();
-        app_data.draw();
+        app_data.draw(&self.viewport.get());
     }
 
     /**
@@ -56,19 +103,275 @@ impl AppState {
      */
     pub fn move_selected_elements(&self, offset_x: i32, offset_y: i32) {
         let mut app_data = self.get_data();
+        app_data.begin_change();
         app_data.move_selected_elements(offset_x, offset_y);
-        app_data.draw();
+        app_data.end_change();
+        app_data.draw(&self.viewport.get());
+        app_data.save_to_local_storage();
+    }
+
+    /**
+     * 翻转选中的元素
+     */
+    pub fn flip_selected_elements(&self, horizontal: bool) {
+        let mut app_data = self.get_data();
+        app_data.begin_change();
+        app_data.flip_selected_elements(horizontal);
+        app_data.end_change();
+        app_data.draw(&self.viewport.get());
+        app_data.save_to_local_storage();
+    }
+
+    /**
+     * 修改选中元素的样式，作为一条撤销记录
+     */
+    pub fn restyle_selected_elements(&self, restyle: impl Fn(&mut ElementConfig)) {
+        let mut app_data = self.get_data();
+        app_data.begin_change();
+        app_data.restyle_selected_elements(restyle);
+        app_data.end_change();
+        app_data.draw(&self.viewport.get());
+        app_data.save_to_local_storage();
+    }
+
+    pub fn undo(&self) {
+        let mut app_data = self.get_data();
+        app_data.undo();
+        app_data.draw(&self.viewport.get());
         app_data.save_to_local_storage();
     }
 
-    pub fn get_data(&self) -> Modify<AppData> {
+    pub fn redo(&self) {
+        let mut app_data = self.get_data();
+        app_data.redo();
+        app_data.draw(&self.viewport.get());
+        app_data.save_to_local_storage();
+    }
+
+    pub fn load_scene(&self, scene: &Scene) {
+        if let Some(color) = &scene.app_state.view_background_color {
+            self.view_bg_color.set(color.clone());
+        }
+        let mut app_data = self.get_data();
+        app_data.begin_change();
+        app_data.replace_elements(scene.to_elements());
+        app_data.end_change();
+        app_data.layout_bound_texts();
+        app_data.draw(&self.viewport.get());
+        app_data.save_to_local_storage();
+    }
+
+    pub fn get_data(&self) -> Modify<'_, AppData> {
         let app_data = self.app_data.modify();
         app_data
     }
 
+    /**
+     * 平移或缩放画布后重绘
+     */
+    pub fn update_viewport(&self, f: impl FnOnce(&mut Viewport)) {
+        f(&mut self.viewport.modify());
+        let app_data = self.get_data();
+        app_data.mark_all_dirty();
+        app_data.draw(&self.viewport.get());
+    }
+
+    /**
+     * 窗口大小或像素比变化后调整画布，并全部重绘
+     */
+    pub fn resize_canvas(&self, width: f64, height: f64, pixel_ratio: f64) {
+        self.canvas_size.set((width, height));
+        self.update_viewport(|viewport| viewport.pixel_ratio = pixel_ratio);
+    }
+
+    /**
+     * 在 (x, y) 处开始输入新的文字
+     */
+    pub fn create_text(&self, x: i32, y: i32) {
+        self.commit_text();
+        self.text_editor.set(Some(TextEditorState {
+            id: None,
+            container_id: None,
+            x,
+            y,
+            angle: 0.0,
+            config: self.get_text_config(),
+            text: String::new(),
+        }));
+    }
+
+    /**
+     * 点中文字时编辑它，点中矩形或椭圆时编辑它的标签，点在空白处时输入新的文字
+     */
+    pub fn edit_text_at(&self, x: i32, y: i32) {
+        let zoom = self.viewport.get().zoom;
+        let target = self
+            .app_data
+            .get()
+            .get_element_by_point(x, y, zoom)
+            .map(|element| (element.id.clone(), element.kind));
+        match target {
+            Some((id, WidgetKind::Text)) => self.edit_text(&id),
+            Some((id, _)) => self.edit_label(&id),
+            None => self.create_text(x, y),
+        }
+    }
+
+    /**
+     * 编辑选中的文字或者选中的矩形、椭圆的标签
+     */
+    pub fn edit_selected_text(&self) {
+        let target = self
+            .app_data
+            .get()
+            .get_transformable_element()
+            .map(|element| (element.id.clone(), element.kind));
+        match target {
+            Some((id, WidgetKind::Text)) => self.edit_text(&id),
+            Some((id, _)) => self.edit_label(&id),
+            None => (),
+        }
+    }
+
+    /**
+     * 编辑矩形或椭圆中的标签，还没有标签时新建一个
+     */
+    pub fn edit_label(&self, container_id: &ElementId) {
+        let app_data = self.app_data.get();
+        if let Some(label) = app_data.get_bound_text(container_id) {
+            let id = label.id.clone();
+            drop(app_data);
+            self.edit_text(&id);
+            return;
+        }
+        let container = match app_data.get_element(container_id) {
+            Some(container) if is_container(container) => container,
+            _ => return,
+        };
+        let editor = TextEditorState {
+            id: None,
+            container_id: Some(container_id.clone()),
+            x: container.rect.start_x,
+            y: container.rect.start_y,
+            angle: container.angle,
+            config: ElementConfig {
+                text_align: TextAlign::Center,
+                ..self.get_text_config()
+            },
+            text: String::new(),
+        };
+        drop(app_data);
+        self.commit_text();
+        self.text_editor.set(Some(editor));
+    }
+
+    /**
+     * 编辑已有的文字元素，编辑时画布上不绘制它
+     */
+    pub fn edit_text(&self, id: &ElementId) {
+        self.commit_text();
+        let mut app_data = self.get_data();
+        let element = match app_data.get_element(id) {
+            Some(element) if element.kind == WidgetKind::Text => element,
+            _ => return,
+        };
+        let editor = TextEditorState {
+            id: Some(id.clone()),
+            container_id: element.container_id.clone(),
+            x: element.rect.start_x,
+            y: element.rect.start_y,
+            angle: element.angle,
+            config: element.config.clone(),
+            text: element.get_text().unwrap_or_default().to_string(),
+        };
+        app_data.set_editing(Some(id.clone()));
+        app_data.draw(&self.viewport.get());
+        drop(app_data);
+        self.text_editor.set(Some(editor));
+    }
+
+    /**
+     * 关闭编辑器并保存文字，保存时重新测量文字的区域，清空文字会删除元素
+     */
+    pub fn commit_text(&self) {
+        let editor = match self.text_editor.get().as_ref().clone() {
+            Some(editor) => editor,
+            None => return,
+        };
+        self.text_editor.set(None);
+        let mut app_data = self.get_data();
+        app_data.set_editing(None);
+        let existing = editor
+            .id
+            .as_ref()
+            .and_then(|id| app_data.get_element(id))
+            .cloned();
+        let text = editor.text;
+        let is_empty = text.trim().is_empty();
+        let is_changed = match &existing {
+            Some(element) => element.get_text() != Some(text.as_str()),
+            None => !is_empty,
+        };
+        if is_changed {
+            app_data.begin_change();
+            match existing {
+                Some(element) if is_empty => app_data.delete_element(&element.id),
+                Some(mut element) => {
+                    element.update_shapes(vec![Primitive::Text { text }]);
+                    place_text(&app_data, &mut element, editor.x, editor.y);
+                    app_data.update_element(element);
+                }
+                None => {
+                    let mut element = Element::new(WidgetKind::Text, editor.config);
+                    element.container_id = editor.container_id;
+                    element.update_shapes(vec![Primitive::Text { text }]);
+                    place_text(&app_data, &mut element, editor.x, editor.y);
+                    // 标签不能单独选中，选中它的容器
+                    let id = element.container_id.clone().unwrap_or(element.id.clone());
+                    app_data.add_element(element);
+                    app_data.select_element(&id, false);
+                }
+            }
+            app_data.end_change();
+            app_data.save_to_local_storage();
+        }
+        app_data.draw(&self.viewport.get());
+    }
+
+    /// Colors and font of a new text element.
+    pub fn get_text_config(&self) -> ElementConfig {
+        ElementConfig {
+            font_family: *self.font_family.get(),
+            font_size: *self.font_size.get(),
+            text_align: *self.text_align.get(),
+            ..ElementConfig::new(
+                self.item_stroke_color.to_string(),
+                self.item_bg_color.to_string(),
+            )
+        }
+    }
+
     pub fn select_all(&self) {
         let mut app_data = self.get_data();
         app_data.select_all_elements();
-        app_data.draw();
+        app_data.draw(&self.viewport.get());
+    }
+}
+
+/**
+ * 标签放在容器中，其它文字从 (x, y) 开始，按文字的大小设置区域
+ */
+fn place_text(app_data: &AppData, element: &mut Element, x: i32, y: i32) {
+    let container = element
+        .container_id
+        .as_ref()
+        .and_then(|id| app_data.get_element(id));
+    match container {
+        Some(container) => layout_bound_text(element, container),
+        None => {
+            let (width, height) =
+                measure_text(element.get_text().unwrap_or_default(), &element.config);
+            element.rect = Rect::new(x, y, x + width.ceil() as i32, y + height.ceil() as i32);
+        }
     }
 }
@@ -0,0 +1,104 @@
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+
+use crate::{
+    draw_scene::get_context,
+    model::{
+        element::{Element, ElementConfig},
+        font::TextAlign,
+    },
+};
+
+thread_local! {
+    /// A detached canvas only used for measuring text.
+    static MEASURE_CONTEXT: CanvasRenderingContext2d = {
+        let window = web_sys::window().expect("no global `window` exists");
+        let document = window.document().expect("should have a document on window");
+        let canvas = document
+            .create_element("canvas")
+            .unwrap()
+            .dyn_into::<HtmlCanvasElement>()
+            .expect("should cast to canvas");
+        get_context(&canvas)
+    };
+}
+
+/**
+ * 每一行文字对齐的 x 坐标和第一行文字的顶部。
+ * 与编辑器中一样，文字在行高内垂直居中
+ */
+pub fn get_text_position(element: &Element) -> (f64, f64) {
+    let rect = element.rect.normalize();
+    let config = &element.config;
+    let x = match config.text_align {
+        TextAlign::Left => rect.start_x as f64,
+        TextAlign::Center => (rect.start_x + rect.end_x) as f64 / 2.0,
+        TextAlign::Right => rect.end_x as f64,
+    };
+    let top = rect.start_y as f64 + (config.get_line_height() - config.font_size) / 2.0;
+    (x, top)
+}
+
+/**
+ * 按元素的字体测量多行文字的宽高，宽度为最长一行的宽度
+ */
+pub fn measure_text(text: &str, config: &ElementConfig) -> (f64, f64) {
+    MEASURE_CONTEXT.with(|ctx| {
+        ctx.set_font(&config.get_font());
+        let width = text
+            .split('\n')
+            .map(|line| ctx.measure_text(line).unwrap().width())
+            .fold(0.0, f64::max);
+        let lines = text.split('\n').count();
+        (width, lines as f64 * config.get_line_height())
+    })
+}
+
+/**
+ * 按宽度自动换行，优先在空格处断开，单个词（或没有空格的中文）超出宽度时按字符断开。
+ * 原有的换行保持不变
+ */
+pub fn wrap_text(text: &str, config: &ElementConfig, max_width: f64) -> String {
+    MEASURE_CONTEXT.with(|ctx| {
+        ctx.set_font(&config.get_font());
+        let fits = |line: &str| ctx.measure_text(line).unwrap().width() <= max_width;
+        let mut lines = Vec::new();
+        text.split('\n').for_each(|paragraph| {
+            let mut line = String::new();
+            paragraph.split(' ').for_each(|word| {
+                let candidate = if line.is_empty() {
+                    word.to_string()
+                } else {
+                    format!("{line} {word}")
+                };
+                if fits(&candidate) {
+                    line = candidate;
+                    return;
+                }
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                word.chars().for_each(|char| {
+                    line.push(char);
+                    // 每行至少保留一个字符
+                    if !fits(&line) && line.chars().count() > 1 {
+                        line.pop();
+                        lines.push(std::mem::replace(&mut line, char.to_string()));
+                    }
+                });
+            });
+            lines.push(line);
+        });
+        lines.join("\n")
+    })
+}
+
+/**
+ * 绘制的文字。标签使用排列时换行的结果，其它文字不换行
+ */
+pub fn get_display_text<'a>(element: &'a Element, text: &'a str) -> &'a str {
+    match (&element.container_id, &element.wrapped_text) {
+        (Some(_), Some(wrapped)) => wrapped,
+        _ => text,
+    }
+}
@@ -0,0 +1,11 @@
+/**
+ * 将点 (x, y) 绕 (center_x, center_y) 顺时针旋转 `angle` 弧度
+ */
+pub fn rotate_point(x: f64, y: f64, center_x: f64, center_y: f64, angle: f64) -> (f64, f64) {
+    let (sin, cos) = angle.sin_cos();
+    let (dx, dy) = (x - center_x, y - center_y);
+    (
+        center_x + dx * cos - dy * sin,
+        center_y + dx * sin + dy * cos,
+    )
+}
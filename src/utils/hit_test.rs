@@ -3,40 +3,44 @@ use crate::{
     widget::arrow::Arrow,
 };
 
-const LINE_THRESHOLD: f32 = 10.0;
+/// How far from a line a click still hits it, in screen pixels.
+pub const LINE_THRESHOLD: f32 = 10.0;
 
-pub fn hit_test(element: &Element, x: i32, y: i32) -> bool {
+/**
+ * 所有的点选和拖动都使用这个方法判断是否点中了元素。
+ * 线条的容差在屏幕上保持不变，并加上线宽的一半；填充的封闭图形点击内部也算点中
+ */
+pub fn hit_test(element: &Element, x: i32, y: i32, zoom: f64) -> bool {
+    let (x, y) = element.to_local_point(x, y);
+    let threshold = LINE_THRESHOLD / zoom as f32 + element.get_stroke_width() as f32 / 2.0;
     match element.kind {
-        WidgetKind::Rectangle => hit_test_by_rectangle(element, x, y),
-        // There doesn't seem to be a closed form solution for the distance between
-        // a point and an ellipse, let's assume it's a rectangle for now...
-        WidgetKind::Ellipse => hit_test_by_rectangle(element, x, y),
-        WidgetKind::Arrow => hit_test_by_arrow(element, x, y),
+        WidgetKind::Rectangle => hit_test_by_rectangle(element, x, y, threshold),
+        WidgetKind::Ellipse => hit_test_by_ellipse(element, x, y, threshold),
+        WidgetKind::Arrow => hit_test_by_arrow(element, x, y, threshold),
         WidgetKind::Text => hit_test_by_text(element, x, y),
-        _ => unimplemented!(),
+        WidgetKind::Selection => false,
     }
 }
 
-fn hit_test_by_arrow(element: &Element, x: i32, y: i32) -> bool {
-    let arrow = Arrow::new(element.rect, "".into(), "".into());
-    let (x1, y1, x2, y2, x3, y3, x4, y4) =
-        arrow.get_lines(element.rect.start_x, element.rect.start_y);
+fn hit_test_by_arrow(element: &Element, x: i32, y: i32, threshold: f32) -> bool {
+    let arrow = Arrow::new(element.rect, "".into(), "".into(), element.seed);
+    let (origin_x, origin_y) = element.origin();
+    let (x1, y1, x2, y2, x3, y3, x4, y4) = arrow.get_lines(origin_x, origin_y);
     let x = x as f32;
     let y = y as f32;
-    tracing::info!(
-        "{:?}",
-        distance_between_point_and_segment(x, y, x1, y1, x2, y2)
-    );
-    distance_between_point_and_segment(x, y, x3, y3, x2, y2) < LINE_THRESHOLD
-        || distance_between_point_and_segment(x, y, x1, y1, x2, y2) < LINE_THRESHOLD
-        || distance_between_point_and_segment(x, y, x4, y4, x2, y2) < LINE_THRESHOLD
+    distance_between_point_and_segment(x, y, x3, y3, x2, y2) < threshold
+        || distance_between_point_and_segment(x, y, x1, y1, x2, y2) < threshold
+        || distance_between_point_and_segment(x, y, x4, y4, x2, y2) < threshold
 }
 
 fn hit_test_by_text(element: &Element, x: i32, y: i32) -> bool {
     element.rect.is_in_point(x, y)
 }
 
-fn hit_test_by_rectangle(element: &Element, x: i32, y: i32) -> bool {
+fn hit_test_by_rectangle(element: &Element, x: i32, y: i32, threshold: f32) -> bool {
+    if element.config.is_filled() && element.rect.is_in_point(x, y) {
+        return true;
+    }
     let x1: f32 = element.rect.start_x as f32;
     let x2 = element.rect.end_x as f32;
     let y1 = element.rect.start_y as f32;
@@ -46,10 +50,59 @@ fn hit_test_by_rectangle(element: &Element, x: i32, y: i32) -> bool {
     // (x1, y1) --A-- (x2, y1)
     //    |D             |B
     // (x1, y2) --C-- (x2, y2)
-    distance_between_point_and_segment(x, y, x1, y1, x2, y1) < LINE_THRESHOLD
-        || distance_between_point_and_segment(x, y, x2, y1, x2, y2) < LINE_THRESHOLD
-        || distance_between_point_and_segment(x, y, x1, y2, x2, y2) < LINE_THRESHOLD
-        || distance_between_point_and_segment(x, y, x1, y1, x1, y2) < LINE_THRESHOLD
+    distance_between_point_and_segment(x, y, x1, y1, x2, y1) < threshold
+        || distance_between_point_and_segment(x, y, x2, y1, x2, y2) < threshold
+        || distance_between_point_and_segment(x, y, x1, y2, x2, y2) < threshold
+        || distance_between_point_and_segment(x, y, x1, y1, x1, y2) < threshold
+}
+
+fn hit_test_by_ellipse(element: &Element, x: i32, y: i32, threshold: f32) -> bool {
+    let rect = element.rect.normalize();
+    let a = rect.get_width() as f32 / 2.0;
+    let b = rect.get_height() as f32 / 2.0;
+    let x = x as f32 - (rect.start_x as f32 + a);
+    let y = y as f32 - (rect.start_y as f32 + b);
+    if element.config.is_filled() && (x / a).powi(2) + (y / b).powi(2) <= 1.0 {
+        return true;
+    }
+    distance_between_point_and_ellipse(x, y, a, b) < threshold
+}
+
+/**
+ * 点到以原点为中心、半轴为 a 和 b 的椭圆的距离。
+ * 没有解析解，这里迭代逼近最近点，见 https://github.com/0xfaded/ellipse_demo
+ */
+fn distance_between_point_and_ellipse(x: f32, y: f32, a: f32, b: f32) -> f32 {
+    // 椭圆是对称的，只需要在第一象限中计算
+    let x = x.abs();
+    let y = y.abs();
+    // 宽或高为 0 时椭圆退化为线段
+    if a == 0.0 || b == 0.0 {
+        return distance_between_point_and_segment(x, y, 0.0, 0.0, a, b);
+    }
+    let mut tx = std::f32::consts::FRAC_1_SQRT_2;
+    let mut ty = std::f32::consts::FRAC_1_SQRT_2;
+
+    for _ in 0..3 {
+        let ex = (a * a - b * b) * tx.powi(3) / a;
+        let ey = (b * b - a * a) * ty.powi(3) / b;
+        let rx = a * tx - ex;
+        let ry = b * ty - ey;
+        let qx = x - ex;
+        let qy = y - ey;
+        let r = rx.hypot(ry);
+        let q = qx.hypot(qy);
+        if q == 0.0 {
+            break;
+        }
+        tx = ((qx * r / q + ex) / a).clamp(0.0, 1.0);
+        ty = ((qy * r / q + ey) / b).clamp(0.0, 1.0);
+        let t = tx.hypot(ty);
+        tx /= t;
+        ty /= t;
+    }
+
+    (x - a * tx).hypot(y - b * ty)
 }
 
 fn distance_between_point_and_segment(x: f32, y: f32, x1: f32, y1: f32, x2: f32, y2: f32) -> f32 {
@@ -82,5 +135,122 @@ fn distance_between_point_and_segment(x: f32, y: f32, x1: f32, y1: f32, x2: f32,
 
     let dx = x - xx;
     let dy = y - yy;
-    ((dx * dx + dy * dy) as f32).sqrt()
+    (dx * dx + dy * dy).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::model::{element::ElementConfig, rect::Rect, transform::Transform};
+
+    fn create_element(kind: WidgetKind, rect: Rect, background: &str) -> Element {
+        let config = ElementConfig::new("#000".into(), background.into());
+        let mut element = Element::new(kind, config);
+        element.transform(Transform::Resize(rect));
+        // 下面的距离按细线设计
+        assert!(element.get_stroke_width() < 4.0);
+        element
+    }
+
+    #[test]
+    fn only_filled_shapes_are_hit_inside() {
+        for kind in [WidgetKind::Rectangle, WidgetKind::Ellipse] {
+            let rect = Rect::new(0, 0, 100, 100);
+            let transparent = create_element(kind, rect, "transparent");
+            let filled = create_element(kind, rect, "#fff");
+            assert!(!hit_test(&transparent, 50, 50, 1.0), "{kind:?}");
+            assert!(hit_test(&filled, 50, 50, 1.0), "{kind:?}");
+            // 边框上两者都能点中，远处都点不中
+            assert!(hit_test(&transparent, 100, 50, 1.0), "{kind:?}");
+            assert!(!hit_test(&filled, 150, 50, 1.0), "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn threshold_stays_the_same_on_screen() {
+        let element = create_element(WidgetKind::Rectangle, Rect::new(0, 0, 100, 100), "");
+        // 场景中离边框 8 和 15 的点
+        assert!(hit_test(&element, 108, 50, 0.5));
+        assert!(hit_test(&element, 108, 50, 1.0));
+        assert!(!hit_test(&element, 108, 50, 4.0));
+        assert!(hit_test(&element, 115, 50, 0.5));
+        assert!(!hit_test(&element, 115, 50, 1.0));
+        assert!(hit_test(&element, 101, 50, 4.0));
+    }
+
+    #[test]
+    fn rotated_element_is_hit_where_it_is_drawn() {
+        let rect = Rect::new(0, 0, 100, 20);
+        let mut element = create_element(WidgetKind::Rectangle, rect, "#fff");
+        assert!(hit_test(&element, 95, 10, 1.0));
+        assert!(!hit_test(&element, 50, -40, 1.0));
+
+        // 绕中心 (50, 10) 转 90° 后竖立，占据 (40, -40) 到 (60, 60)
+        element.transform(Transform::Rotate(PI / 2.0));
+        assert!(!hit_test(&element, 95, 10, 1.0));
+        assert!(hit_test(&element, 50, -40, 1.0));
+        assert!(hit_test(&element, 50, 55, 1.0));
+        assert!(!hit_test(&element, 50, 75, 1.0));
+    }
+
+    /// Distance to many points on the outline, slow but obviously right.
+    fn sample_distance(x: f32, y: f32, a: f32, b: f32) -> f32 {
+        (0..100_000)
+            .map(|i| {
+                let t = i as f32 / 100_000.0 * std::f32::consts::TAU;
+                (x - a * t.cos()).hypot(y - b * t.sin())
+            })
+            .fold(f32::MAX, f32::min)
+    }
+
+    #[test]
+    fn ellipse_distance_matches_sampling() {
+        let (a, b) = (100.0, 50.0);
+        let points = [
+            // 在边框上
+            (100.0, 0.0),
+            (0.0, -50.0),
+            (100.0 * 0.6f32.cos(), 50.0 * 0.6f32.sin()),
+            // 内部
+            (0.0, 0.0),
+            (90.0, 0.0),
+            (-30.0, 20.0),
+            // 外部
+            (150.0, 0.0),
+            (0.0, 80.0),
+            (-200.0, -120.0),
+            // 外接矩形的角
+            (100.0, 50.0),
+            (-100.0, 50.0),
+        ];
+        for (x, y) in points {
+            let distance = distance_between_point_and_ellipse(x, y, a, b);
+            let expected = sample_distance(x, y, a, b);
+            assert!(
+                (distance - expected).abs() < 0.5,
+                "({x}, {y}): {distance} != {expected}"
+            );
+        }
+        assert!(distance_between_point_and_ellipse(100.0, 0.0, a, b) < 0.01);
+        assert!((distance_between_point_and_ellipse(0.0, 0.0, a, b) - 50.0).abs() < 0.01);
+        assert!((distance_between_point_and_ellipse(90.0, 0.0, a, b) - 10.0).abs() < 0.01);
+        assert!((distance_between_point_and_ellipse(150.0, 0.0, a, b) - 50.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn flat_ellipse_is_a_segment() {
+        let cases = [
+            ((10.0, 20.0), (0.0, 50.0), 10.0),
+            ((0.0, -70.0), (0.0, 50.0), 20.0),
+            ((-50.0, 10.0), (100.0, 0.0), 10.0),
+            ((3.0, 4.0), (0.0, 0.0), 5.0),
+        ];
+        for ((x, y), (a, b), expected) in cases {
+            let distance = distance_between_point_and_ellipse(x, y, a, b);
+            assert!(!distance.is_nan());
+            assert!((distance - expected).abs() < 1e-4, "({x}, {y}) to {a}x{b}");
+        }
+    }
 }
@@ -1 +1,3 @@
+pub mod geometry;
 pub mod hit_test;
+pub mod random;
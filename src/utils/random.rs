@@ -0,0 +1,62 @@
+use std::cell::Cell;
+
+thread_local! {
+    static STATE: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Fixes the sequence returned by `next_u64`, e.g. to get stable ids in tests.
+pub fn set_seed(seed: u64) {
+    STATE.with(|state| state.set(Some(seed)));
+}
+
+/// SplitMix64 over a counter, so values never repeat within one session.
+pub fn next_u64() -> u64 {
+    STATE.with(|state| {
+        let current = state.get().unwrap_or_else(initial_seed);
+        let next = current.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        state.set(Some(next));
+
+        let mut z = next;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    })
+}
+
+#[cfg(target_arch = "wasm32")]
+fn initial_seed() -> u64 {
+    let high = (js_sys::Math::random() * 2f64.powi(32)) as u64;
+    let low = (js_sys::Math::random() * 2f64.powi(32)) as u64;
+    high << 32 | low
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn initial_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        set_seed(7);
+        let first: Vec<u64> = (0..5).map(|_| next_u64()).collect();
+        set_seed(7);
+        let second: Vec<u64> = (0..5).map(|_| next_u64()).collect();
+        assert_eq!(first, second);
+        set_seed(8);
+        assert_ne!(next_u64(), first[0]);
+    }
+
+    #[test]
+    fn values_do_not_repeat() {
+        set_seed(0);
+        let values: std::collections::HashSet<u64> = (0..10_000).map(|_| next_u64()).collect();
+        assert_eq!(values.len(), 10_000);
+    }
+}
@@ -1,10 +1,73 @@
-use crate::store::AppState;
+use crate::{
+    model::font::{FONT_FAMILIES, FONT_SIZES, TEXT_ALIGNS},
+    store::AppState,
+};
 use sycamore::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Event, HtmlInputElement, HtmlSelectElement};
 
 #[component]
 pub fn ConfigBar<G: Html>(ctx: Scope) -> View<G> {
     let app_state = use_context::<AppState>(ctx);
 
+    // 下拉框的值为选项在列表中的序号，选项在设置值之后才添加，所以还需要 `selected`
+    let font_family_value =
+        create_signal(ctx, index_of(&FONT_FAMILIES, &app_state.font_family.get()));
+    create_effect(ctx, move || {
+        if let Some(font_family) = FONT_FAMILIES.get(parse_index(&font_family_value.get())) {
+            app_state.font_family.set(*font_family);
+        }
+    });
+    let font_size_value = create_signal(ctx, index_of(&FONT_SIZES, &app_state.font_size.get()));
+    create_effect(ctx, move || {
+        if let Some(font_size) = FONT_SIZES.get(parse_index(&font_size_value.get())) {
+            app_state.font_size.set(*font_size);
+        }
+    });
+    let text_align_value = create_signal(ctx, index_of(&TEXT_ALIGNS, &app_state.text_align.get()));
+    create_effect(ctx, move || {
+        if let Some(text_align) = TEXT_ALIGNS.get(parse_index(&text_align_value.get())) {
+            app_state.text_align.set(*text_align);
+        }
+    });
+
+    let font_family_options = View::new_fragment(
+        FONT_FAMILIES
+            .iter()
+            .enumerate()
+            .map(|(index, font_family)| {
+                view!(ctx, option(
+                    value=index.to_string(),
+                    selected=*font_family == *app_state.font_family.get(),
+                ) { (font_family.to_string()) })
+            })
+            .collect(),
+    );
+    let font_size_options = View::new_fragment(
+        FONT_SIZES
+            .iter()
+            .enumerate()
+            .map(|(index, font_size)| {
+                view!(ctx, option(
+                    value=index.to_string(),
+                    selected=*font_size == *app_state.font_size.get(),
+                ) { (format!("{font_size}px")) })
+            })
+            .collect(),
+    );
+    let text_align_options = View::new_fragment(
+        TEXT_ALIGNS
+            .iter()
+            .enumerate()
+            .map(|(index, text_align)| {
+                view!(ctx, option(
+                    value=index.to_string(),
+                    selected=*text_align == *app_state.text_align.get(),
+                ) { (text_align.as_str()) })
+            })
+            .collect(),
+    );
+
     view!(ctx, div(class="config-bar") {
         label(class="mx-2 inline-flex items-center cursor-pointer select-none") {
             input(
@@ -17,6 +80,11 @@ pub fn ConfigBar<G: Html>(ctx: Scope) -> View<G> {
             input(
                 type="color",
                 bind:value=app_state.item_stroke_color,
+                // 选中的元素也使用新的颜色
+                on:change=move |event: Event| {
+                    let color = get_input_value(&event);
+                    app_state.restyle_selected_elements(|config| config.item_stroke_color = color.clone());
+                },
             )
             "item stroke color"
         }
@@ -24,8 +92,82 @@ pub fn ConfigBar<G: Html>(ctx: Scope) -> View<G> {
             input(
                 type="color",
                 bind:value=app_state.item_bg_color,
+                on:change=move |event: Event| {
+                    let color = get_input_value(&event);
+                    app_state.restyle_selected_elements(|config| config.item_bg_color = color.clone());
+                },
             )
             "item background color"
         }
+        label(class="mx-2 select-none") {
+            "font:"
+            select(
+                class="border",
+                bind:value=font_family_value,
+                on:change=move |event: Event| {
+                    if let Some(font_family) = FONT_FAMILIES.get(get_select_index(&event)) {
+                        app_state.restyle_selected_elements(|config| config.font_family = *font_family);
+                    }
+                },
+            ) {
+                (font_family_options)
+            }
+        }
+        label(class="mx-2 select-none") {
+            "font size:"
+            select(
+                class="border",
+                bind:value=font_size_value,
+                on:change=move |event: Event| {
+                    if let Some(font_size) = FONT_SIZES.get(get_select_index(&event)) {
+                        app_state.restyle_selected_elements(|config| config.font_size = *font_size);
+                    }
+                },
+            ) {
+                (font_size_options)
+            }
+        }
+        label(class="mx-2 select-none") {
+            "text align:"
+            select(
+                class="border",
+                bind:value=text_align_value,
+                on:change=move |event: Event| {
+                    if let Some(text_align) = TEXT_ALIGNS.get(get_select_index(&event)) {
+                        app_state.restyle_selected_elements(|config| config.text_align = *text_align);
+                    }
+                },
+            ) {
+                (text_align_options)
+            }
+        }
     })
 }
+
+fn get_input_value(event: &Event) -> String {
+    event
+        .target()
+        .unwrap()
+        .unchecked_into::<HtmlInputElement>()
+        .value()
+}
+
+fn get_select_index(event: &Event) -> usize {
+    let select = event
+        .target()
+        .unwrap()
+        .unchecked_into::<HtmlSelectElement>();
+    parse_index(&select.value())
+}
+
+fn index_of<T: PartialEq>(options: &[T], value: &T) -> String {
+    options
+        .iter()
+        .position(|option| option == value)
+        .unwrap_or(0)
+        .to_string()
+}
+
+fn parse_index(value: &str) -> usize {
+    value.parse().unwrap_or(0)
+}
@@ -1,4 +1,13 @@
-use crate::store::AppState;
+use crate::{
+    draw_scene::{draw_element, get_context},
+    draw_svg::draw_svg,
+    model::{
+        element::{get_bounds, Element},
+        rect::Rect,
+        AppData,
+    },
+    store::{AppState, ExportConfig},
+};
 use sycamore::prelude::*;
 use wasm_bindgen::{JsCast, JsValue};
 
@@ -13,17 +22,31 @@ pub fn ExportTool<G: Html>(ctx: Scope) -> View<G> {
         export_config.padding = input_value.get().parse().unwrap_or(0);
     });
 
+    let scale_value = create_signal(ctx, app_state.export_config.get().scale.to_string());
+
+    create_effect(ctx, move || {
+        let mut export_config = app_state.export_config.modify();
+        export_config.scale = scale_value.get().parse().unwrap_or(1);
+    });
+
     view!(ctx, div(class="exportWrapper") {
         button(
             class="bg-blue-500 hover:bg-blue-700 text-white  py-1 px-1 rounded mx-2",
             on:click=move |_| {
                 tracing::info!("export to png: {:?}", app_state.export_config.get());
-                app_state.clear_selection_elements();
-                export_as_png(&app_state);
+                export_as_png(app_state);
             },
         ) {
             "Export to png"
         }
+        button(
+            class="bg-blue-500 hover:bg-blue-700 text-white  py-1 px-1 rounded mx-2",
+            on:click=move |_| {
+                export_as_svg(app_state);
+            },
+        ) {
+            "Export to svg"
+        }
         label(class="mx-2 cursor-pointer select-none") {
             input(
                 type="checkbox",
@@ -35,6 +58,25 @@ pub fn ExportTool<G: Html>(ctx: Scope) -> View<G> {
             )
             "background"
         }
+        button(
+            class="bg-blue-500 hover:bg-blue-700 text-white  py-1 px-1 rounded mx-2",
+            on:click=move |_| {
+                copy_svg_to_clipboard(app_state);
+            },
+        ) {
+            "Copy to clipboard"
+        }
+        label(class="mx-2 cursor-pointer select-none") {
+            input(
+                type="checkbox",
+                checked=app_state.export_config.get().selected_only,
+                on:click=move |_| {
+                    let mut export_config = app_state.export_config.modify();
+                    export_config.selected_only = !export_config.selected_only;
+                },
+            )
+            "selected only"
+        }
         label(class="mx-2 cursor-pointer select-none") {
             input(
                 type="checkbox",
@@ -55,6 +97,14 @@ pub fn ExportTool<G: Html>(ctx: Scope) -> View<G> {
             )
             "px)"
         }
+        label(class="mx-2 select-none") {
+            "scale:"
+            select(class="border", bind:value=scale_value) {
+                option(value="1") { "1x" }
+                option(value="2") { "2x" }
+                option(value="3") { "3x" }
+            }
+        }
     })
 }
 
@@ -62,78 +112,122 @@ fn export_as_png(app_state: &AppState) {
     let app_data = app_state.app_data.get();
     let export_config = app_state.export_config.get();
     let view_bg_color = app_state.view_bg_color.get();
-
-    let mut sub_canvas_x1 = i32::MAX;
-    let mut sub_canvas_x2 = 0;
-    let mut sub_canvas_y1 = i32::MAX;
-    let mut sub_canvas_y2 = 0;
-
-    app_data.elements.iter().for_each(|element| {
-        sub_canvas_x1 = sub_canvas_x1.min(element.rect.start_x.min(element.rect.end_x));
-        sub_canvas_x2 = sub_canvas_x2.max(element.rect.start_x.max(element.rect.end_x));
-        sub_canvas_y1 = sub_canvas_y1.min(element.rect.start_y.min(element.rect.end_y));
-        sub_canvas_y2 = sub_canvas_y2.max(element.rect.start_y.max(element.rect.end_y));
-    });
+    let elements = get_export_elements(&app_data, &export_config);
+    let area = match get_export_area(&elements, &export_config, app_state) {
+        Some(area) => area,
+        None => return,
+    };
+    let scale = export_config.scale.max(1);
 
     let window = web_sys::window().expect("no global `window` exists");
     let document = window.document().expect("should have a document on window");
-
-    let main_canvas = document
-        .get_element_by_id("canvas")
-        .unwrap()
+    let canvas = document
+        .create_element("canvas")
+        .expect("should create canvas")
         .dyn_into::<web_sys::HtmlCanvasElement>()
         .expect("should cast to canvas");
+    canvas.set_width(area.get_width() as u32 * scale);
+    canvas.set_height(area.get_height() as u32 * scale);
+    let canvas_ctx = get_context(&canvas);
+    canvas_ctx.scale(scale.into(), scale.into()).unwrap();
+    canvas_ctx
+        .translate((-area.start_x).into(), (-area.start_y).into())
+        .unwrap();
 
-    if export_config.visible_area_only {
-        let canvas = document
-            .create_element("canvas")
-            .expect("should create canvas")
-            .dyn_into::<web_sys::HtmlCanvasElement>()
-            .expect("should cast to canvas");
-        canvas.set_attribute("style", "display: none").unwrap();
-        let _ = document.body().unwrap().append_child(&canvas);
-        let width = sub_canvas_x2 - sub_canvas_x1 + (export_config.padding * 2) as i32;
-        let height = sub_canvas_y2 - sub_canvas_y1 + (export_config.padding * 2) as i32;
-        let padding = export_config.padding as i32;
+    if export_config.background {
+        canvas_ctx.set_fill_style(&JsValue::from_str(view_bg_color.as_str()));
+        canvas_ctx.fill_rect(
+            area.start_x.into(),
+            area.start_y.into(),
+            area.get_width().into(),
+            area.get_height().into(),
+        );
+    }
+    elements
+        .iter()
+        .for_each(|element| draw_element(&canvas_ctx, element));
 
-        canvas
-            .set_attribute("width", format!("{}px", width).as_str())
-            .unwrap();
-        canvas
-            .set_attribute("height", format!("{}px", height).as_str())
-            .unwrap();
-        let canvas_ctx = canvas
-            .get_context("2d")
-            .expect("should get context")
-            .unwrap()
-            .dyn_into::<web_sys::CanvasRenderingContext2d>()
-            .expect("should cast to context");
-
-        if export_config.background {
-            canvas_ctx.set_fill_style(&JsValue::from_str(view_bg_color.as_str()));
-            canvas_ctx.fill_rect(0.0, 0.0, width as f64, height as f64);
-        }
+    download_file(canvas.to_data_url().unwrap().as_str(), "export.png");
+}
+
+fn export_as_svg(app_state: &AppState) {
+    let svg = match get_export_svg(app_state) {
+        Some(svg) => svg,
+        None => return,
+    };
+    let url = format!(
+        "data:image/svg+xml;charset=utf-8,{}",
+        js_sys::encode_uri_component(&svg)
+    );
+    download_file(&url, "export.svg");
+}
+
+fn copy_svg_to_clipboard(app_state: &AppState) {
+    let svg = match get_export_svg(app_state) {
+        Some(svg) => svg,
+        None => return,
+    };
+    // `navigator.clipboard` is still behind web-sys' unstable APIs
+    let window = web_sys::window().expect("no global `window` exists");
+    let clipboard = js_sys::Reflect::get(&window.navigator(), &"clipboard".into()).unwrap();
+    if clipboard.is_undefined() {
+        tracing::error!("clipboard is not available");
+        return;
+    }
+    let write_text = js_sys::Reflect::get(&clipboard, &"writeText".into())
+        .unwrap()
+        .dyn_into::<js_sys::Function>()
+        .expect("should be a function");
+    if let Err(err) = write_text.call1(&clipboard, &JsValue::from_str(&svg)) {
+        tracing::error!("can't copy svg to clipboard: {:?}", err);
+    }
+}
+
+fn get_export_svg(app_state: &AppState) -> Option<String> {
+    let app_data = app_state.app_data.get();
+    let export_config = app_state.export_config.get();
+    let view_bg_color = app_state.view_bg_color.get();
+    let elements = get_export_elements(&app_data, &export_config);
+    let area = get_export_area(&elements, &export_config, app_state)?;
+    let background = export_config.background.then_some(view_bg_color.as_str());
+    Some(draw_svg(&elements, area, background))
+}
 
-        let _ = canvas_ctx
-            .draw_image_with_html_canvas_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
-                &main_canvas,
-                (sub_canvas_x1 - padding).into(),
-                (sub_canvas_y1 - padding).into(),
-                width.into(),
-                height.into(),
-                0.0,
-                0.0,
-                width.into(),
-                height.into(),
-            );
-        export_png_file(canvas.to_data_url().unwrap().as_str());
-        canvas.remove();
+fn get_export_elements<'a>(
+    app_data: &'a AppData,
+    export_config: &ExportConfig,
+) -> Vec<&'a Element> {
+    if export_config.selected_only {
+        app_data.get_selected_elements_with_labels()
+    } else {
+        app_data.elements.iter().collect()
+    }
+}
+
+/**
+ * 导出的区域：只导出元素所在的区域时为元素的边界加上 padding，否则为画布当前可见的区域
+ */
+fn get_export_area(
+    elements: &[&Element],
+    export_config: &ExportConfig,
+    app_state: &AppState,
+) -> Option<Rect> {
+    if export_config.visible_area_only {
+        let bounds = get_bounds(elements)?;
+        let padding = export_config.padding as i32;
+        Some(Rect::new(
+            bounds.start_x - padding,
+            bounds.start_y - padding,
+            bounds.end_x + padding,
+            bounds.end_y + padding,
+        ))
     } else {
-        export_png_file(main_canvas.to_data_url().unwrap().as_str());
+        let (width, height) = *app_state.canvas_size.get();
+        Some(app_state.viewport.get().get_visible_rect(width, height))
     }
 }
 
-fn export_png_file(url: &str) {
+pub fn download_file(url: &str, file_name: &str) {
     let window = web_sys::window().expect("no global `window` exists");
     let document = window.document().expect("should have a document on window");
     let link = document
@@ -141,7 +235,7 @@ fn export_png_file(url: &str) {
         .expect("should create a")
         .dyn_into::<web_sys::HtmlAnchorElement>()
         .expect("should cast to a");
-    link.set_attribute("download", "export.png").unwrap();
+    link.set_attribute("download", file_name).unwrap();
     link.set_attribute("href", url).unwrap();
     link.click();
     link.remove();
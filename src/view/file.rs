@@ -0,0 +1,60 @@
+use crate::{model::scene::Scene, store::AppState, view::export::download_file};
+use sycamore::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{Event, HtmlInputElement};
+
+const SCENE_FILE_NAME: &str = "scene.excalidraw";
+
+#[component]
+pub fn FileTool<G: Html>(ctx: Scope) -> View<G> {
+    let app_state = use_context::<AppState>(ctx);
+
+    view!(ctx, div(class="fileWrapper") {
+        label(class="bg-blue-500 hover:bg-blue-700 text-white py-1 px-1 rounded mx-2 cursor-pointer") {
+            "Open"
+            input(
+                class="hidden",
+                type="file",
+                accept=".excalidraw,.json,application/json",
+                on:change=move |event: Event| {
+                    let input = event.target().unwrap().unchecked_into::<HtmlInputElement>();
+                    if let Some(file) = input.files().and_then(|files| files.get(0)) {
+                        let app_state = app_state.clone();
+                        spawn_local(async move {
+                            let text = match JsFuture::from(file.text()).await {
+                                Ok(text) => text,
+                                Err(err) => {
+                                    tracing::error!("can't read {}: {:?}", file.name(), err);
+                                    return;
+                                }
+                            };
+                            match Scene::parse(&text.as_string().unwrap_or_default()) {
+                                Ok(scene) => app_state.load_scene(&scene),
+                                Err(err) => tracing::error!("can't open scene: {}", err),
+                            }
+                        });
+                    }
+                    input.set_value("");
+                },
+            )
+        }
+        button(
+            class="bg-blue-500 hover:bg-blue-700 text-white py-1 px-1 rounded mx-2",
+            on:click=move |_| save_scene(app_state),
+        ) {
+            "Save"
+        }
+    })
+}
+
+fn save_scene(app_state: &AppState) {
+    let app_data = app_state.app_data.get();
+    let scene = Scene::new(&app_data.elements, &app_state.view_bg_color.get());
+    let data = serde_json::to_string_pretty(&scene).unwrap();
+    let url = format!(
+        "data:application/json;charset=utf-8,{}",
+        js_sys::encode_uri_component(&data)
+    );
+    download_file(&url, SCENE_FILE_NAME);
+}
@@ -1,3 +1,5 @@
 pub mod config_bar;
 pub mod export;
+pub mod file;
+pub mod text_editor;
 pub mod toolbar;
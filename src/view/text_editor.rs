@@ -0,0 +1,131 @@
+use sycamore::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlTextAreaElement, KeyboardEvent};
+
+use crate::{
+    model::{
+        bound_text::{get_bound_text_rect, get_max_text_width},
+        viewport::Viewport,
+        AppData,
+    },
+    store::{AppState, TextEditorState},
+    text_layout::measure_text,
+};
+
+/// Room after the longest line so the caret at its end stays visible.
+const CARET_SPACE: f64 = 4.0;
+
+/// A textarea over the canvas, placed and rotated like the text it edits.
+#[component]
+pub fn TextEditor<G: Html>(ctx: Scope) -> View<G> {
+    let app_state = use_context::<AppState>(ctx);
+    let textarea_ref = create_node_ref(ctx);
+
+    let style = create_memo(ctx, move || match app_state.text_editor.get().as_ref() {
+        Some(editor) => get_style(editor, &app_state.viewport.get(), &app_state.app_data.get()),
+        None => "display: none".to_string(),
+    });
+    // 只在打开编辑器时变化，输入文字时不变
+    let opened = create_selector(ctx, move || {
+        app_state.text_editor.get().as_ref().as_ref().map(|editor| {
+            let id = editor.id.clone().or(editor.container_id.clone());
+            (id, editor.x, editor.y)
+        })
+    });
+
+    let view = view!(ctx,
+        textarea(
+            ref=textarea_ref,
+            class="absolute m-0 p-0 border-0 outline-none resize-none overflow-hidden bg-transparent break-words",
+            style=style.get(),
+            spellcheck="false",
+            on:input=move |_| {
+                let textarea: HtmlTextAreaElement = textarea_ref.get::<DomNode>().unchecked_into();
+                if let Some(editor) = app_state.text_editor.modify().as_mut() {
+                    editor.text = textarea.value();
+                }
+            },
+            on:blur=move |_| app_state.commit_text(),
+            on:keydown=move |event: web_sys::Event| {
+                // 输入时不触发画布的快捷键
+                event.stop_propagation();
+                let event: KeyboardEvent = event.unchecked_into();
+                let command_key = event.meta_key() || event.ctrl_key();
+                if event.key() == "Escape" || (event.key() == "Enter" && command_key) {
+                    event.prevent_default();
+                    app_state.commit_text();
+                }
+            },
+            on:keyup=|event: web_sys::Event| event.stop_propagation(),
+            on:copy=|event: web_sys::Event| event.stop_propagation(),
+            on:paste=|event: web_sys::Event| event.stop_propagation(),
+        )
+    );
+
+    // 打开编辑器时填入原来的文字并获取焦点
+    create_effect(ctx, move || {
+        if opened.get().is_none() {
+            return;
+        }
+        let text = match app_state.text_editor.get_untracked().as_ref() {
+            Some(editor) => editor.text.clone(),
+            None => return,
+        };
+        let textarea: HtmlTextAreaElement = textarea_ref.get::<DomNode>().unchecked_into();
+        textarea.set_value(&text);
+        let _ = textarea.focus();
+        textarea.select();
+    });
+
+    view
+}
+
+/**
+ * 编辑器与文字在画布上的位置、字体和角度保持一致
+ */
+fn get_style(editor: &TextEditorState, viewport: &Viewport, app_data: &AppData) -> String {
+    let config = &editor.config;
+    let container = editor
+        .container_id
+        .as_ref()
+        .and_then(|id| app_data.get_element(id));
+    // 标签在容器的宽度内换行，其它文字不换行
+    let (x, y, width, height, caret_space, white_space) = match container {
+        Some(container) => {
+            let rect = get_bound_text_rect(&editor.text, config, container);
+            let width = get_max_text_width(container);
+            let (center_x, _) = container.rect.get_center();
+            let height = rect.get_height() as f64;
+            (
+                center_x - width / 2.0,
+                rect.start_y as f64,
+                width,
+                height,
+                0.0,
+                "pre-wrap",
+            )
+        }
+        None => {
+            let (width, height) = measure_text(&editor.text, config);
+            let (x, y) = (editor.x as f64, editor.y as f64);
+            (x, y, width, height, CARET_SPACE, "pre")
+        }
+    };
+    let zoom = viewport.zoom;
+    let (left, top) = viewport.to_screen_f64(x, y);
+    format!(
+        "left: {left}px; top: {top}px; width: {}px; height: {}px; white-space: {white_space}; \
+         font: {}px {}; line-height: {}px; text-align: {}; color: {}; \
+         transform: rotate({}rad); transform-origin: {}px {}px",
+        (width + caret_space) * zoom,
+        height * zoom,
+        config.font_size * zoom,
+        config.font_family.css_name(),
+        config.get_line_height() * zoom,
+        config.text_align.as_str(),
+        config.item_stroke_color,
+        editor.angle,
+        width / 2.0 * zoom,
+        height / 2.0 * zoom,
+    )
+}
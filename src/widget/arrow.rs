@@ -1,4 +1,7 @@
-use crate::{model::rect::Rect, rough::Rough};
+use crate::{
+    model::{primitive::Primitive, rect::Rect},
+    rough::Rough,
+};
 
 use super::shape::Shape;
 
@@ -7,20 +10,22 @@ pub struct Arrow {
     rect: Rect,
     item_stroke_color: String,
     item_bg_color: String,
+    seed: u32,
 }
 
 impl Arrow {
-    pub fn new(rect: Rect, item_stroke_color: String, item_bg_color: String) -> Arrow {
+    pub fn new(rect: Rect, item_stroke_color: String, item_bg_color: String, seed: u32) -> Arrow {
         Arrow {
             rect,
             item_stroke_color,
             item_bg_color,
+            seed,
         }
     }
 }
 
 impl Shape for Arrow {
-    fn get_config(&self) -> Vec<String> {
+    fn get_config(&self) -> Vec<Primitive> {
         let (x1, y1, x2, y2, x3, y3, x4, y4) = self.get_lines(0, 0);
         let config1 = Rough::generator_line(
             x3,
@@ -29,6 +34,7 @@ impl Shape for Arrow {
             y2,
             self.item_stroke_color.clone(),
             self.item_bg_color.clone(),
+            self.seed,
         );
         let config2 = Rough::generator_line(
             x1,
@@ -37,6 +43,7 @@ impl Shape for Arrow {
             y2,
             self.item_stroke_color.clone(),
             self.item_bg_color.clone(),
+            self.seed.wrapping_add(1),
         );
         let config3 = Rough::generator_line(
             x4,
@@ -45,8 +52,12 @@ impl Shape for Arrow {
             y2,
             self.item_stroke_color.clone(),
             self.item_bg_color.clone(),
+            self.seed.wrapping_add(2),
         );
-        [config1, config2, config3].to_vec()
+        [config1, config2, config3]
+            .into_iter()
+            .flat_map(Primitive::from_drawable)
+            .collect()
     }
 }
 
@@ -60,14 +71,14 @@ impl Arrow {
         let x2 = x1 + width;
         let y2 = y1 + height;
 
-        let size = 30 as f32; // pixels
-        let distance = ((x2 - x1).powf(2.0) as f32 + (y2 - y1).powf(2.0) as f32).sqrt();
+        let size = 30_f32; // pixels
+        let distance = ((x2 - x1).powf(2.0) + (y2 - y1).powf(2.0)).sqrt();
         // Scale down the arrow until we hit a certain size so that it doesn't look weird
         let min_size = size.min(distance / 2.0);
         let xs = x2 - ((x2 - x1) / distance) * min_size;
         let ys = y2 - ((y2 - y1) / distance) * min_size;
 
-        let angle = 20 as f32; // degrees
+        let angle = 20_f32; // degrees
         let [x3, y3] = rotate(xs, ys, x2, y2, (-angle * std::f32::consts::PI) / 180.0);
         let [x4, y4] = rotate(xs, ys, x2, y2, (angle * std::f32::consts::PI) / 180.0);
         (x1, y1, x2, y2, x3, y3, x4, y4)
@@ -1,4 +1,7 @@
-use crate::{model::rect::Rect, rough::Rough};
+use crate::{
+    model::{primitive::Primitive, rect::Rect},
+    rough::Rough,
+};
 
 use super::shape::Shape;
 
@@ -7,28 +10,31 @@ pub struct Ellipse {
     rect: Rect,
     item_stroke_color: String,
     item_bg_color: String,
+    seed: u32,
 }
 
 impl Ellipse {
-    pub fn new(rect: Rect, item_stroke_color: String, item_bg_color: String) -> Ellipse {
+    pub fn new(rect: Rect, item_stroke_color: String, item_bg_color: String, seed: u32) -> Ellipse {
         Ellipse {
             rect,
             item_bg_color,
             item_stroke_color,
+            seed,
         }
     }
 }
 
 impl Shape for Ellipse {
-    fn get_config(&self) -> Vec<String> {
-        let config_string = Rough::generator_ellipse(
+    fn get_config(&self) -> Vec<Primitive> {
+        let drawable = Rough::generator_ellipse(
             self.rect.get_width() / 2,
             self.rect.get_height() / 2,
             self.rect.get_width(),
             self.rect.get_height(),
             self.item_stroke_color.clone(),
             self.item_bg_color.clone(),
+            self.seed,
         );
-        [config_string].to_vec()
+        Primitive::from_drawable(drawable)
     }
 }
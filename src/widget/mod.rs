@@ -13,11 +13,19 @@ pub fn create_widget(
     rect: Rect,
     item_stroke_color: String,
     item_bg_color: String,
+    seed: u32,
 ) -> Box<dyn Shape> {
     match widget_type {
-        WidgetKind::Rectangle => Box::new(Rectangle::new(rect, item_stroke_color, item_bg_color)),
-        WidgetKind::Ellipse => Box::new(Ellipse::new(rect, item_stroke_color, item_bg_color)),
-        WidgetKind::Arrow => Box::new(arrow::Arrow::new(rect, item_stroke_color, item_bg_color)),
+        WidgetKind::Rectangle => {
+            Box::new(Rectangle::new(rect, item_stroke_color, item_bg_color, seed))
+        }
+        WidgetKind::Ellipse => Box::new(Ellipse::new(rect, item_stroke_color, item_bg_color, seed)),
+        WidgetKind::Arrow => Box::new(arrow::Arrow::new(
+            rect,
+            item_stroke_color,
+            item_bg_color,
+            seed,
+        )),
         WidgetKind::Selection => Box::new(selection::Selection::new()),
         _ => unimplemented!(),
     }
@@ -1,4 +1,7 @@
-use crate::{model::rect::Rect, rough::Rough};
+use crate::{
+    model::{primitive::Primitive, rect::Rect},
+    rough::Rough,
+};
 
 use super::shape::Shape;
 
@@ -7,28 +10,36 @@ pub struct Rectangle {
     rect: Rect,
     item_stroke_color: String,
     item_bg_color: String,
+    seed: u32,
 }
 
 impl Rectangle {
-    pub fn new(rect: Rect, item_stroke_color: String, item_bg_color: String) -> Rectangle {
+    pub fn new(
+        rect: Rect,
+        item_stroke_color: String,
+        item_bg_color: String,
+        seed: u32,
+    ) -> Rectangle {
         Rectangle {
             rect,
             item_stroke_color,
             item_bg_color,
+            seed,
         }
     }
 }
 
 impl Shape for Rectangle {
-    fn get_config(&self) -> Vec<String> {
-        let config_string = Rough::generator_rectangle(
+    fn get_config(&self) -> Vec<Primitive> {
+        let drawable = Rough::generator_rectangle(
             0,
             0,
             self.rect.get_width(),
             self.rect.get_height(),
             self.item_stroke_color.clone(),
             self.item_bg_color.clone(),
+            self.seed,
         );
-        [config_string].to_vec()
+        Primitive::from_drawable(drawable)
     }
 }
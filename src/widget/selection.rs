@@ -1,8 +1,16 @@
+use crate::model::primitive::Primitive;
+
 use super::shape::Shape;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Selection {}
 
+impl Default for Selection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Selection {
     pub fn new() -> Selection {
         Selection {}
@@ -10,7 +18,7 @@ impl Selection {
 }
 
 impl Shape for Selection {
-    fn get_config(&self) -> Vec<String> {
+    fn get_config(&self) -> Vec<Primitive> {
         [].to_vec()
     }
 }
@@ -1,3 +1,5 @@
+use crate::model::primitive::Primitive;
+
 pub trait Shape {
-    fn get_config(&self) -> Vec<String>;
+    fn get_config(&self) -> Vec<Primitive>;
 }